@@ -1,5 +1,15 @@
 pub mod args {
-    use std::{env::args, fmt::Display};
+    use std::{
+        env::{self, args},
+        fmt::Display,
+        path::PathBuf,
+        thread,
+        time::Duration,
+    };
+
+    use crate::config;
+    use crate::logger::console::ColorChoice;
+    use crate::logger::writer::{LogFormat, LogOptions, LogStatus, Retention};
 
     pub fn get_args() -> Result<Options, ParsingError> {
         let args = args();
@@ -10,14 +20,61 @@ pub mod args {
         let cmd_args_vec: Vec<String> = args.collect();
         let argparser = ArgsParser::new(cmd_args_vec);
 
-        let options = match argparser.parse_options() {
-            Ok(some) => some,
-            Err(e) => return Err(e),
+        // a broken config file only stops the commands that read it, `tmplt config set` can still fix it
+        let options = match config::load_files() {
+            Ok(files) => argparser.parse_options(&files)?,
+            Err(e) => {
+                let options = argparser.parse_options(&[])?;
+                if options.command.reads_config() {
+                    return Err(ParsingError {
+                        reason: e.to_string(),
+                    });
+                }
+                options
+            }
         };
 
         Ok(options)
     }
 
+    /// parses a duration like `500ms`, `60s`, `2m` or `1h`, a number without a unit is in seconds,
+    /// `None` if it isn't a duration or is too long to be represented
+    pub fn parse_duration(value: &str) -> Option<Duration> {
+        let unit_start = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (num, unit) = value.split_at(unit_start);
+        let num = num.parse::<u64>().ok()?;
+
+        match unit {
+            "ms" => Some(Duration::from_millis(num)),
+            "" | "s" => Some(Duration::from_secs(num)),
+            "m" => num.checked_mul(60).map(Duration::from_secs),
+            "h" => num.checked_mul(60 * 60).map(Duration::from_secs),
+            _ => None,
+        }
+    }
+
+    /// replaces a leading `~` with the home directory, the shell doesn't do it after `--flag=`
+    /// and the config files aren't read by a shell at all
+    pub fn expand_home(path: &str) -> PathBuf {
+        let rest = match path.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with(['/', std::path::MAIN_SEPARATOR]) => {
+                rest.trim_start_matches(['/', std::path::MAIN_SEPARATOR])
+            }
+            _ => return PathBuf::from(path),
+        };
+        let home = ["HOME", "USERPROFILE"]
+            .iter()
+            .filter_map(env::var_os)
+            .find(|home| !home.is_empty());
+
+        match home {
+            Some(home) => PathBuf::from(home).join(rest),
+            None => PathBuf::from(path),
+        }
+    }
+
     #[derive(Debug, Clone, Default)]
     pub struct ParsingError {
         reason: String,
@@ -65,6 +122,11 @@ pub mod args {
         Generate,
         Help,
         New,
+        Config,
+        List,
+        Info,
+        Check,
+        Fmt,
         #[default]
         UnknownArg,
         // todo  make it possible to pull other files from a github repo or a url
@@ -74,63 +136,572 @@ pub mod args {
     pub enum Flags {
         SaveLogs(bool),
         TaskPerBatch(usize),
+        CleanEnv(bool),
+        Timeout(Duration),
+        RawOutput(bool),
+        Jobs(usize),
+        HeavyJobs(usize),
+        Report(String),
+        Output(OutputFormat),
+        LogFile(PathBuf),
+        LogFormat(LogFormat),
+        LogRetention(Retention),
+        Verbosity(LogStatus),
+        Color(ColorChoice),
+        /// a variable of the `__VAR` section set from the command line, `--var name=value`
+        Var(String, String),
+        LogDir(PathBuf),
+        Shell(String),
+        Conflict(ConflictPolicy),
+        /// runs the template without checking it first, `--no-preflight`
+        NoPreflight(bool),
+        TemplatePath(Vec<PathBuf>),
+        /// `tmplt config set` writes to the project config, `--project`
+        Project,
+        /// `tmplt check` fails on warnings too, `--deny-warnings`
+        DenyWarnings,
+        /// `tmplt fmt` only tells which templates aren't formatted, `--check`
+        FormatCheck,
+        Version,
+        /// prints the help of the command, `--help`
+        Help,
         UnknownFlag(ParsingError),
     }
 
+    /// what tmplt prints while generating, `json` prints events for other tools to read
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub enum OutputFormat {
+        #[default]
+        Text,
+        Json,
+    }
+
+    /// what happens when a file or a directory of the `__CWD` section already exists
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ConflictPolicy {
+        /// the task fails
+        Error,
+        /// the file or directory is left as it is
+        Skip,
+        /// the file is emptied, an existing directory is used as it is
+        Overwrite,
+    }
+
     impl Flags {
         pub fn into_flag<T: Clone>(inner: T) -> Flag<T> {
             Flag::new(inner)
         }
     }
 
-    impl From<String> for Flags {
-        fn from(value: String) -> Self {
-            match value.as_str() {
-                str if str.starts_with("--task-num") || str.starts_with("--batch-size") => {
-                    let flag = str
-                        .split('=')
-                        .map(|v| v.to_string())
-                        .collect::<Vec<String>>();
-                    if flag.len() == 1 {
-                        return Self::UnknownFlag(ParsingError { reason : "misused the batch size flag, example'--batch-size=50'".to_string()});
-                    }
+    /// a command of the cli, `tmplt help` and `tmplt help <command>` are written from these
+    #[derive(Debug)]
+    pub struct CommandSpec {
+        pub name: &'static str,
+        pub aliases: &'static [&'static str],
+        pub usage: &'static str,
+        pub about: &'static str,
+        /// the most arguments (that aren't flags) the command takes
+        pub max_args: usize,
+        pub flags: &'static [FlagSpec],
+    }
+
+    /// a flag of a command, `value` is what is expected after the `=` (`None` for a switch)
+    #[derive(Debug)]
+    pub struct FlagSpec {
+        pub name: &'static str,
+        pub aliases: &'static [&'static str],
+        pub value: Option<&'static str>,
+        pub default: &'static str,
+        pub about: &'static str,
+    }
+
+    impl CommandSpec {
+        pub fn matches(&self, name: &str) -> bool {
+            self.name == name || self.aliases.contains(&name)
+        }
+    }
+
+    impl FlagSpec {
+        pub fn matches(&self, name: &str) -> bool {
+            self.name == name || self.aliases.contains(&name)
+        }
+    }
+
+    /// the flags every command accepts, they can also be passed before the command
+    pub const GLOBAL_FLAGS: &[FlagSpec] = &[
+        FlagSpec {
+            name: "--help",
+            aliases: &["-h"],
+            value: None,
+            default: "false",
+            about: "print the usage and the flags of the command",
+        },
+        FlagSpec {
+            name: "--version",
+            aliases: &["-V"],
+            value: None,
+            default: "false",
+            about: "print the version of tmplt",
+        },
+    ];
+
+    /// shared by generate and list
+    const TEMPLATE_PATH_FLAG: FlagSpec = FlagSpec {
+        name: "--template-path",
+        aliases: &[],
+        value: Some("DIRS"),
+        default: "none",
+        about: "more directories to search the templates in, separated like PATH (':', or ';' on windows)",
+    };
+
+    pub const CONFIG_FLAGS: &[FlagSpec] = &[FlagSpec {
+        name: "--project",
+        aliases: &[],
+        value: None,
+        default: "false",
+        about: "set the value in the .tmpltrc of the project instead of the user config",
+    }];
+
+    pub const CHECK_FLAGS: &[FlagSpec] = &[
+        FlagSpec {
+            name: "--deny-warnings",
+            aliases: &[],
+            value: None,
+            default: "false",
+            about: "fail (exit with 1) if a warning is found",
+        },
+        TEMPLATE_PATH_FLAG,
+    ];
+
+    pub const FMT_FLAGS: &[FlagSpec] = &[
+        FlagSpec {
+            name: "--check",
+            aliases: &[],
+            value: None,
+            default: "false",
+            about: "don't write anything, fail (exit with 1) if a template isn't formatted",
+        },
+        TEMPLATE_PATH_FLAG,
+    ];
+
+    pub const GENERATE_FLAGS: &[FlagSpec] = &[
+        FlagSpec {
+            name: "--var",
+            aliases: &[],
+            value: Some("NAME=VALUE"),
+            default: "none",
+            about:
+                "set a variable of the __VAR section (or override it), can be passed more than once",
+        },
+        TEMPLATE_PATH_FLAG,
+        FlagSpec {
+            name: "--save-logs",
+            aliases: &["--sl", "--logged"],
+            value: None,
+            default: "false",
+            about: "save the output of the run into a new log file in the logs directory",
+        },
+        FlagSpec {
+            name: "--log-file",
+            aliases: &[],
+            value: Some("FILE"),
+            default: "none",
+            about: "save the logs into this file instead of the logs directory",
+        },
+        FlagSpec {
+            name: "--log-dir",
+            aliases: &[],
+            value: Some("DIR"),
+            default: "the logs directory of the platform",
+            about: "the directory the log files are saved in",
+        },
+        FlagSpec {
+            name: "--log-format",
+            aliases: &[],
+            value: Some("text|json"),
+            default: "text",
+            about: "how each line of the log file is written",
+        },
+        FlagSpec {
+            name: "--log-retention",
+            aliases: &[],
+            value: Some("N|Nd"),
+            default: "30",
+            about: "how many log files (or days of logs with `d`) are kept in the logs directory",
+        },
+        FlagSpec {
+            name: "--quiet",
+            aliases: &["-q"],
+            value: None,
+            default: "false",
+            about: "only print the warnings, the errors and what the scripts write to stderr",
+        },
+        FlagSpec {
+            name: "--verbose",
+            aliases: &["-v"],
+            value: None,
+            default: "false",
+            about: "also print the syntax tree and the interpreted template",
+        },
+        FlagSpec {
+            name: "-vv",
+            aliases: &[],
+            value: None,
+            default: "false",
+            about: "also print which worker runs which tasks",
+        },
+        FlagSpec {
+            name: "--color",
+            aliases: &[],
+            value: Some("auto|always|never"),
+            default: "auto",
+            about: "color the output, `auto` colors it in a terminal unless NO_COLOR is set",
+        },
+        FlagSpec {
+            name: "--batch-size",
+            aliases: &["--task-num"],
+            value: Some("N"),
+            default: "10",
+            about: "the number of tasks in a batch, a batch is executed by one worker",
+        },
+        FlagSpec {
+            name: "--jobs",
+            aliases: &["-j"],
+            value: Some("N"),
+            default: "the number of CPUs",
+            about: "the number of workers executing batches at the same time",
+        },
+        FlagSpec {
+            name: "--heavy-jobs",
+            aliases: &[],
+            value: Some("N"),
+            default: "1",
+            about: "the number of heavy scripts running at the same time",
+        },
+        FlagSpec {
+            name: "--clean-env",
+            aliases: &[],
+            value: None,
+            default: "false",
+            about:
+                "run the scripts with an empty environment, only PATH and the __ENV section are set",
+        },
+        FlagSpec {
+            name: "--shell",
+            aliases: &[],
+            value: Some("SHELL"),
+            default: "sh -c (cmd /C on windows)",
+            about: "the shell the scripts run in, followed by its arguments, i.e \"bash -c\"",
+        },
+        FlagSpec {
+            name: "--conflict",
+            aliases: &[],
+            value: Some("error|skip|overwrite"),
+            default: "none",
+            about: "what to do with the files and directories that already exist, by default a file is overwritten and a directory fails",
+        },
+        FlagSpec {
+            name: "--no-preflight",
+            aliases: &[],
+            value: None,
+            default: "false",
+            about: "run the template without checking first that the programs, the files and the directories it needs are there",
+        },
+        FlagSpec {
+            name: "--timeout",
+            aliases: &[],
+            value: Some("DURATION"),
+            default: "none",
+            about: "stop the scripts running longer than this (units: ms, s, m, h)",
+        },
+        FlagSpec {
+            name: "--report",
+            aliases: &[],
+            value: Some("FILE"),
+            default: "none",
+            about: "write the outcome of the run to a JSON file",
+        },
+        FlagSpec {
+            name: "--output",
+            aliases: &["-o"],
+            value: Some("text|json"),
+            default: "text",
+            about: "`json` prints one JSON event per line instead of the usual output",
+        },
+        FlagSpec {
+            name: "--raw-output",
+            aliases: &[],
+            value: None,
+            default: "false",
+            about: "print the output of the scripts exactly as they wrote it",
+        },
+    ];
+
+    pub const COMMANDS: &[CommandSpec] = &[
+        CommandSpec {
+            name: "generate",
+            aliases: &["gen", "g"],
+            usage: "tmplt generate <file.tmplt or template name> [flags]",
+            about: "interpret the file and generate the directory it describes, also runs its scripts",
+            max_args: 1,
+            flags: GENERATE_FLAGS,
+        },
+        CommandSpec {
+            name: "new",
+            aliases: &["init"],
+            usage: "tmplt new [file.tmplt]",
+            about: "initialize a new .tmplt file (new.tmplt by default) demonstrating the sections and keywords",
+            max_args: 1,
+            flags: &[],
+        },
+        CommandSpec {
+            name: "help",
+            aliases: &["h"],
+            usage: "tmplt help [command]",
+            about: "print this help, or the usage and the flags of a command",
+            max_args: 1,
+            flags: &[],
+        },
+        CommandSpec {
+            name: "list",
+            aliases: &["ls"],
+            usage: "tmplt list [flags]",
+            about: "list the templates of the search path, they can be generated with their name (tmplt gen <name>)",
+            max_args: 0,
+            flags: &[TEMPLATE_PATH_FLAG],
+        },
+        CommandSpec {
+            name: "info",
+            aliases: &[],
+            usage: "tmplt info <file.tmplt or template name> [flags]",
+            about: "show the metadata of a template (its __META section) and if its requirements are installed",
+            max_args: 1,
+            flags: &[TEMPLATE_PATH_FLAG],
+        },
+        CommandSpec {
+            name: "check",
+            aliases: &["validate"],
+            usage: "tmplt check [files.tmplt or template names...] [flags]",
+            about: "check templates without running them, every template of tmplt list if none is passed",
+            max_args: usize::MAX,
+            flags: CHECK_FLAGS,
+        },
+        CommandSpec {
+            name: "fmt",
+            aliases: &["format"],
+            usage: "tmplt fmt [files.tmplt or template names...] [flags]",
+            about: "rewrite templates in the canonical style, every template of tmplt list if none is passed",
+            max_args: usize::MAX,
+            flags: FMT_FLAGS,
+        },
+        CommandSpec {
+            name: "config",
+            aliases: &[],
+            usage: "tmplt config list | get <key> | set <key> <value> [--project]",
+            about: "show or change the defaults of the flags of generate, saved in the user config or in .tmpltrc",
+            max_args: 3,
+            flags: CONFIG_FLAGS,
+        },
+    ];
+
+    pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+        COMMANDS.iter().find(|command| command.matches(name))
+    }
 
-                    let num = match flag[1].parse::<usize>() {
+    impl Flags {
+        /// the flag from its spec and what was passed with it, `ArgsParser` already checked
+        /// that a value was passed if (and only if) the flag takes one
+        pub fn parse(spec: &FlagSpec, value: Option<&str>) -> Self {
+            match spec.name {
+                "--batch-size" => {
+                    let value = match value {
+                        Some(value) => value,
+                        None => return Self::UnknownFlag(ParsingError { reason : "misused the batch size flag, example'--batch-size=50'".to_string()}),
+                    };
+
+                    let num = match value.parse::<usize>() {
                         Ok(n) => n,
                         Err(_) => return Self::UnknownFlag(ParsingError { reason : "should've passed a number after the '='".to_string()}),
                     };
 
                     Self::TaskPerBatch(num)
                 },
-                "--sl" | "--save-logs" | "--logged" => Self::SaveLogs(true),
-                &_ => Self::UnknownFlag(ParsingError { reason : "unknow flag has been passed, to check all the avaliable flags use 'tmplt help' and check the generate command section".to_string()}),
+                "--jobs" | "--heavy-jobs" => {
+                    match value.and_then(|v| v.parse::<usize>().ok()) {
+                        Some(n) if n > 0 && spec.name == "--jobs" => Self::Jobs(n),
+                        Some(n) if n > 0 => Self::HeavyJobs(n),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the jobs flag, should've passed a number bigger than 0 after the '=', example '--jobs=4'".to_string()}),
+                    }
+                },
+                "--output" => {
+                    match value {
+                        Some("text") => Self::Output(OutputFormat::Text),
+                        Some("json") => Self::Output(OutputFormat::Json),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the output flag, the output can be 'text' or 'json', example '--output=json'".to_string()}),
+                    }
+                },
+                "--color" => {
+                    match value {
+                        Some("auto") => Self::Color(ColorChoice::Auto),
+                        Some("always") => Self::Color(ColorChoice::Always),
+                        Some("never") => Self::Color(ColorChoice::Never),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the color flag, the color can be 'auto', 'always' or 'never', example '--color=never'".to_string()}),
+                    }
+                },
+                "--log-file" => {
+                    match value {
+                        Some(path) if !path.is_empty() => Self::LogFile(expand_home(path)),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the log file flag, should've passed a file after the '=', example '--log-file=tmplt.log'".to_string()}),
+                    }
+                },
+                "--log-format" => {
+                    match value {
+                        Some("text") => Self::LogFormat(LogFormat::Text),
+                        Some("json") => Self::LogFormat(LogFormat::Json),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the log format flag, the format can be 'text' or 'json', example '--log-format=json'".to_string()}),
+                    }
+                },
+                "--log-retention" => {
+                    let value = value.unwrap_or_default();
+                    let retention = match value.strip_suffix('d') {
+                        Some(days) => days.parse::<u64>().ok().map(Retention::Days),
+                        None => value.parse::<usize>().ok().filter(|n| *n > 0).map(Retention::Count),
+                    };
+
+                    match retention {
+                        Some(retention) => Self::LogRetention(retention),
+                        None => Self::UnknownFlag(ParsingError { reason : "misused the log retention flag, should've passed a number of logs or of days, example '--log-retention=10' or '--log-retention=7d'".to_string()}),
+                    }
+                },
+                "--report" => {
+                    match value {
+                        Some(path) if !path.is_empty() => Self::Report(path.to_string()),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the report flag, should've passed a file after the '=', example '--report=report.json'".to_string()}),
+                    }
+                },
+                "--timeout" => {
+                    match value.and_then(parse_duration) {
+                        Some(timeout) => Self::Timeout(timeout),
+                        None => Self::UnknownFlag(ParsingError { reason : "misused the timeout flag, example '--timeout=60s' (units: ms, s, m, h)".to_string()}),
+                    }
+                },
+                "--save-logs" => Self::SaveLogs(true),
+                "--quiet" => Self::Verbosity(LogStatus::Warning),
+                "--verbose" => Self::Verbosity(LogStatus::Debug),
+                "-vv" => Self::Verbosity(LogStatus::Trace),
+                "--clean-env" => Self::CleanEnv(true),
+                "--no-preflight" => Self::NoPreflight(true),
+                "--raw-output" => Self::RawOutput(true),
+                "--var" => {
+                    match value.and_then(|v| v.split_once('=')) {
+                        Some((name, value)) if !name.trim().is_empty() => Self::Var(name.trim().to_string(), value.to_string()),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the var flag, should've passed a name and a value, example '--var package=github.com/me/app'".to_string()}),
+                    }
+                },
+                "--log-dir" => {
+                    match value {
+                        Some(dir) if !dir.is_empty() => Self::LogDir(expand_home(dir)),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the log dir flag, should've passed a directory, example '--log-dir=logs'".to_string()}),
+                    }
+                },
+                "--shell" => {
+                    match value {
+                        Some(shell) if !shell.trim().is_empty() => Self::Shell(shell.trim().to_string()),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the shell flag, should've passed a shell, example '--shell=\"bash -c\"'".to_string()}),
+                    }
+                },
+                "--conflict" => {
+                    match value {
+                        Some("error") => Self::Conflict(ConflictPolicy::Error),
+                        Some("skip") => Self::Conflict(ConflictPolicy::Skip),
+                        Some("overwrite") => Self::Conflict(ConflictPolicy::Overwrite),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the conflict flag, the policy can be 'error', 'skip' or 'overwrite', example '--conflict=skip'".to_string()}),
+                    }
+                },
+                "--template-path" => {
+                    match value {
+                        Some(dirs) if !dirs.is_empty() => Self::TemplatePath(env::split_paths(dirs).map(|dir| expand_home(&dir.to_string_lossy())).collect()),
+                        _ => Self::UnknownFlag(ParsingError { reason : "misused the template path flag, should've passed directories, example '--template-path=~/templates'".to_string()}),
+                    }
+                },
+                "--project" => Self::Project,
+                "--deny-warnings" => Self::DenyWarnings,
+                "--check" => Self::FormatCheck,
+                "--version" => Self::Version,
+                "--help" => Self::Help,
+                // every flag of `GENERATE_FLAGS` and `GLOBAL_FLAGS` is handled above
+                name => unreachable!("the {name} flag isn't handled"),
             }
         }
     }
 
     impl From<String> for CommandLineArgs {
         fn from(value: String) -> Self {
-            match value.as_str() {
-                "gen" | "generate" | "g" => Self::Generate,
-                "help" | "h" => Self::Help,
-                "init" | "new" => Self::New,
-                &_ => Self::UnknownArg,
+            match find_command(&value).map(|command| command.name) {
+                Some("generate") => Self::Generate,
+                Some("help") => Self::Help,
+                Some("new") => Self::New,
+                Some("config") => Self::Config,
+                Some("list") => Self::List,
+                Some("info") => Self::Info,
+                Some("check") => Self::Check,
+                Some("fmt") => Self::Fmt,
+                _ => Self::UnknownArg,
             }
         }
     }
 
+    impl CommandLineArgs {
+        /// whether the config files set the defaults of the command
+        pub fn reads_config(&self) -> bool {
+            matches!(
+                self,
+                Self::Generate | Self::List | Self::Info | Self::Check | Self::Fmt
+            )
+        }
+    }
+
+    /// parses the arguments following the grammar `tmplt [global flags] <command> [argument] [flags]`,
+    /// a flag takes its value with `--flag=value` or `--flag value` (`-j4` or `-j 4` for a short one),
+    /// and everything after `--` is an argument
     #[derive(Debug, Clone)]
     pub struct ArgsParser {
         args: Vec<String>,
-        flags: Vec<String>,
     }
 
     #[derive(Debug, Clone, Default)]
     pub struct Options {
         command: CommandLineArgs,
         file: Option<String>,
+        /// the command `tmplt help <command>` is about
+        help_topic: Option<String>,
+        /// the arguments passed after the command that aren't flags
+        arguments: Vec<String>,
+        vars: Flag<Vec<(String, String)>>,
+        version: Flag<bool>,
+        help: Flag<bool>,
+        log_dir: Flag<Option<PathBuf>>,
+        shell: Flag<Option<String>>,
+        conflict: Flag<Option<ConflictPolicy>>,
+        no_preflight: Flag<bool>,
+        template_paths: Flag<Vec<PathBuf>>,
+        project: Flag<bool>,
+        deny_warnings: Flag<bool>,
+        format_check: Flag<bool>,
         save_logs: Flag<bool>,
         task_num: Flag<usize>,
+        clean_env: Flag<bool>,
+        timeout: Flag<Option<Duration>>,
+        raw_output: Flag<bool>,
+        jobs: Flag<usize>,
+        heavy_jobs: Flag<usize>,
+        report: Flag<Option<String>>,
+        output: Flag<OutputFormat>,
+        log_file: Flag<Option<PathBuf>>,
+        log_format: Flag<LogFormat>,
+        log_retention: Flag<Retention>,
+        verbosity: Flag<Option<LogStatus>>,
+        color: Flag<ColorChoice>,
     }
 
     impl Options {
@@ -142,10 +713,125 @@ pub mod args {
             self.file.clone()
         }
 
+        pub fn get_help_topic(&self) -> Option<&str> {
+            self.help_topic.as_deref()
+        }
+
+        /// the variables passed with `--var`, in the order they were passed
+        pub fn get_vars_flag(&self) -> &Vec<(String, String)> {
+            self.vars.unwrap_ref()
+        }
+
+        pub fn get_version_flag(&self) -> &bool {
+            self.version.unwrap_ref()
+        }
+
+        pub fn get_arguments(&self) -> &Vec<String> {
+            &self.arguments
+        }
+
+        /// the shell and its arguments the scripts run in, i.e `["bash", "-c"]`
+        pub fn get_shell_flag(&self) -> Option<Vec<String>> {
+            self.shell
+                .unwrap_ref()
+                .as_ref()
+                .map(|shell| shell.split_whitespace().map(|v| v.to_string()).collect())
+        }
+
+        pub fn get_conflict_flag(&self) -> &Option<ConflictPolicy> {
+            self.conflict.unwrap_ref()
+        }
+
+        pub fn get_no_preflight_flag(&self) -> &bool {
+            self.no_preflight.unwrap_ref()
+        }
+
+        /// the directories of `--template-path`
+        pub fn get_template_paths_flag(&self) -> &Vec<PathBuf> {
+            self.template_paths.unwrap_ref()
+        }
+
+        pub fn get_project_flag(&self) -> &bool {
+            self.project.unwrap_ref()
+        }
+
+        pub fn get_deny_warnings_flag(&self) -> &bool {
+            self.deny_warnings.unwrap_ref()
+        }
+
+        pub fn get_format_check_flag(&self) -> &bool {
+            self.format_check.unwrap_ref()
+        }
+
         pub fn get_save_logs_flag(&self) -> &bool {
             self.save_logs.unwrap_ref()
         }
 
+        /// the logs are saved with `--logged` or when a log file is given
+        pub fn is_logging_enabled(&self) -> bool {
+            *self.get_save_logs_flag() || self.log_file.unwrap_ref().is_some()
+        }
+
+        /// the least important status that gets printed, from `-q`/`-v`/`-vv`, then `TMPLT_LOG`, `INFO` by default
+        pub fn get_log_level(&self) -> LogStatus {
+            if let Some(level) = self.verbosity.unwrap_ref() {
+                return level.clone();
+            }
+
+            env::var("TMPLT_LOG")
+                .ok()
+                .and_then(|name| LogStatus::from_name(&name))
+                .unwrap_or(LogStatus::Info)
+        }
+
+        /// when the output is colored, `auto` colors it when printing to a terminal unless `NO_COLOR` is set
+        pub fn get_color_flag(&self) -> &ColorChoice {
+            self.color.unwrap_ref()
+        }
+
+        pub fn get_log_options(&self) -> LogOptions {
+            LogOptions {
+                file: self.log_file.unwrap_ref().clone(),
+                dir: self.log_dir.unwrap_ref().clone(),
+                format: *self.log_format.unwrap_ref(),
+                retention: *self.log_retention.unwrap_ref(),
+            }
+        }
+
+        pub fn get_clean_env_flag(&self) -> &bool {
+            self.clean_env.unwrap_ref()
+        }
+
+        pub fn get_raw_output_flag(&self) -> &bool {
+            self.raw_output.unwrap_ref()
+        }
+
+        pub fn get_output_flag(&self) -> &OutputFormat {
+            self.output.unwrap_ref()
+        }
+
+        /// the file the JSON report of the run is written to
+        pub fn get_report_flag(&self) -> &Option<String> {
+            self.report.unwrap_ref()
+        }
+
+        pub fn get_timeout_flag(&self) -> &Option<Duration> {
+            self.timeout.unwrap_ref()
+        }
+
+        /// the number of tasks running at the same time, the number of CPUs by default
+        pub fn get_jobs_flag(&self) -> usize {
+            match self.jobs.unwrap_ref() {
+                0 => thread::available_parallelism().map_or(1, |n| n.get()),
+                jobs => *jobs,
+            }
+        }
+
+        /// the number of heavy tasks (i.e `npm install`) running at the same time, 1 by default
+        pub fn get_heavy_jobs_flag(&self) -> usize {
+            (*self.heavy_jobs.unwrap_ref()).max(1)
+        }
+
         pub fn get_batch_size_flag(&self) -> &usize {
             let size = self.task_num.unwrap_ref();
             if *size == 0 {
@@ -158,55 +844,432 @@ pub mod args {
 
     impl ArgsParser {
         pub fn new(mut args: Vec<String>) -> Self {
-            let flags = args
-                .iter()
-                .filter(|v| v.starts_with("--"))
-                .map(|v| v.to_owned())
-                .collect();
             args.remove(0);
-            let args: Vec<String> = args
-                .iter()
-                .filter(|v| !v.starts_with("--"))
-                .cloned()
-                .collect();
 
-            ArgsParser { args, flags }
+            ArgsParser { args }
         }
 
-        pub fn parse_options(&self) -> Result<Options, ParsingError> {
-            let file = self
-                .args
-                .iter()
-                .filter(|v| v.contains(".tmplt"))
-                .cloned()
-                .collect::<Vec<String>>();
-            let mut flags_vec: Vec<Flags> = vec![];
+        /// `config` are the config files in the order they're applied, see `config::load_files`
+        pub fn parse_options(
+            &self,
+            config: &[config::ConfigFile],
+        ) -> Result<Options, ParsingError> {
+            let mut options: Options = Options::default();
+            let mut command: Option<&'static CommandSpec> = None;
+            let mut positionals: Vec<String> = vec![];
+            let mut flags: Vec<Flags> = vec![];
 
-            for f in self.flags.clone() {
-                let flag = Flags::from(f);
-                flags_vec.push(flag)
+            let mut args = self.args.iter();
+            while let Some(arg) = args.next() {
+                if arg == "--" {
+                    positionals.extend(args.by_ref().cloned());
+                    break;
+                }
+                if !arg.starts_with('-') || arg == "-" {
+                    match command {
+                        Some(_) => positionals.push(arg.clone()),
+                        None => {
+                            command = Some(find_command(arg).ok_or_else(|| unknown_command(arg))?)
+                        }
+                    }
+                    continue;
+                }
+
+                let specs = command.map_or(&[][..], |command| command.flags);
+                let (spec, value) = find_flag(arg, specs)?;
+                let value = match (spec.value, value) {
+                    (Some(_), Some(value)) => Some(value),
+                    (Some(expected), None) => match args.next() {
+                        Some(value) => Some(value.clone()),
+                        None => return Err(ParsingError { reason: format!("the {} flag should be followed by a value ({expected}), example '{} {expected}'", spec.name, spec.name) }),
+                    },
+                    (None, Some(_)) => return Err(ParsingError { reason: format!("the {} flag doesn't take a value", spec.name) }),
+                    (None, None) => None,
+                };
+                flags.push(Flags::parse(spec, value.as_deref()))
+            }
+
+            // the config files set the defaults of generate (and of list, info, check and fmt for the template path),
+            // the flags are applied after them
+            if command.is_some_and(|command| {
+                ["generate", "list", "info", "check", "fmt"].contains(&command.name)
+            }) {
+                for file in config {
+                    for entry in file.entries.iter() {
+                        let flag =
+                            config_flag(&entry.key, &entry.value).map_err(|e| ParsingError {
+                                reason: config::ConfigError::new(&file.path, entry.line, &e.reason)
+                                    .to_string(),
+                            })?;
+                        if let Some(flag) = flag {
+                            options.apply(flag)?
+                        }
+                    }
+                }
+            }
+            for flag in flags {
+                options.apply(flag)?
             }
-            let mut options: Options = Options::default();
 
-            if !file.is_empty() {
-                options.file = Some(file[0].clone());
+            if *options.get_version_flag() {
+                return Ok(options);
             }
 
-            if self.args.is_empty() {
-                return Err(ParsingError { reason: "no command has been passed, help: Existing commands; help, generate and new".to_owned() });
+            // `tmplt gen --help` is `tmplt help gen`
+            if *options.help.unwrap_ref() {
+                options.command = CommandLineArgs::Help;
+                options.help_topic = command.map(|command| command.name.to_string());
+                return Ok(options);
             }
 
-            options.command = CommandLineArgs::from(self.args[0].clone());
+            let command = match command {
+                Some(command) => command,
+                None => return Err(ParsingError { reason: "no command has been passed, help: Existing commands; help, generate, new, list, info, check, fmt and config".to_owned() }),
+            };
+            options.command = CommandLineArgs::from(command.name.to_string());
 
-            for flag in flags_vec {
-                match flag {
-                    Flags::SaveLogs(inner) => options.save_logs = Flags::into_flag(inner),
-                    Flags::TaskPerBatch(inner) => options.task_num = Flags::into_flag(inner),
-                    Flags::UnknownFlag(error) => return Err(error),
+            if positionals.len() > command.max_args {
+                return Err(ParsingError {
+                    reason: format!(
+                        "unexpected argument '{}', usage: {}",
+                        positionals[command.max_args], command.usage
+                    ),
+                });
+            }
+            match options.command {
+                CommandLineArgs::Help => options.help_topic = positionals.first().cloned(),
+                CommandLineArgs::Generate | CommandLineArgs::New | CommandLineArgs::Info => {
+                    options.file = positionals.first().cloned()
                 }
+                _ => (),
             }
+            options.arguments = positionals;
 
             Ok(options)
         }
     }
+
+    impl Options {
+        fn apply(&mut self, flag: Flags) -> Result<(), ParsingError> {
+            match flag {
+                Flags::SaveLogs(inner) => self.save_logs = Flags::into_flag(inner),
+                Flags::TaskPerBatch(inner) => self.task_num = Flags::into_flag(inner),
+                Flags::CleanEnv(inner) => self.clean_env = Flags::into_flag(inner),
+                Flags::Timeout(inner) => self.timeout = Flags::into_flag(Some(inner)),
+                Flags::RawOutput(inner) => self.raw_output = Flags::into_flag(inner),
+                Flags::Jobs(inner) => self.jobs = Flags::into_flag(inner),
+                Flags::HeavyJobs(inner) => self.heavy_jobs = Flags::into_flag(inner),
+                Flags::Report(inner) => self.report = Flags::into_flag(Some(inner)),
+                Flags::Output(inner) => self.output = Flags::into_flag(inner),
+                Flags::LogFile(inner) => self.log_file = Flags::into_flag(Some(inner)),
+                Flags::LogFormat(inner) => self.log_format = Flags::into_flag(inner),
+                Flags::LogRetention(inner) => self.log_retention = Flags::into_flag(inner),
+                Flags::Verbosity(inner) => self.verbosity = Flags::into_flag(Some(inner)),
+                Flags::Color(inner) => self.color = Flags::into_flag(inner),
+                Flags::Var(name, value) => {
+                    let mut vars = self.vars.unwrap_ref().clone();
+                    vars.push((name, value));
+                    self.vars = Flags::into_flag(vars)
+                }
+                Flags::LogDir(inner) => self.log_dir = Flags::into_flag(Some(inner)),
+                Flags::Shell(inner) => self.shell = Flags::into_flag(Some(inner)),
+                Flags::Conflict(inner) => self.conflict = Flags::into_flag(Some(inner)),
+                Flags::NoPreflight(inner) => self.no_preflight = Flags::into_flag(inner),
+                Flags::TemplatePath(inner) => self.template_paths = Flags::into_flag(inner),
+                Flags::Project => self.project = Flags::into_flag(true),
+                Flags::DenyWarnings => self.deny_warnings = Flags::into_flag(true),
+                Flags::FormatCheck => self.format_check = Flags::into_flag(true),
+                Flags::Version => self.version = Flags::into_flag(true),
+                Flags::Help => self.help = Flags::into_flag(true),
+                Flags::UnknownFlag(error) => return Err(error),
+            }
+            Ok(())
+        }
+    }
+
+    /// the flag a key of the config files sets, a switch (i.e `save-logs`) is set with `true`
+    /// and `false` leaves it off (`None`)
+    pub fn config_flag(key: &str, value: &str) -> Result<Option<Flags>, ParsingError> {
+        let spec = config_key(key)?;
+        let flag = match (spec.value, value) {
+            (Some(_), value) => Flags::parse(spec, Some(value)),
+            (None, "true") => Flags::parse(spec, None),
+            (None, "false") => return Ok(None),
+            (None, _) => {
+                return Err(ParsingError {
+                    reason: format!("'{key}' is set with true or false"),
+                })
+            }
+        };
+        match flag {
+            Flags::UnknownFlag(error) => Err(error),
+            flag => Ok(Some(flag)),
+        }
+    }
+
+    /// the flag of a key of the config files, every flag of generate except `--var` is a key
+    pub fn config_key(key: &str) -> Result<&'static FlagSpec, ParsingError> {
+        let name = format!("--{}", config::normalize_key(key));
+        // a short flag is a key too, `j` is `jobs`
+        let short = format!("-{}", config::normalize_key(key));
+        match GENERATE_FLAGS
+            .iter()
+            .find(|spec| (spec.matches(&name) || spec.matches(&short)) && spec.name != "--var")
+        {
+            Some(spec) => Ok(spec),
+            None => {
+                let keys = GENERATE_FLAGS
+                    .iter()
+                    .filter(|spec| spec.name.starts_with("--") && spec.name != "--var")
+                    .map(|spec| spec.name)
+                    .collect::<Vec<&str>>();
+                let reason = match suggest(&name, &keys) {
+                    Some(suggestion) => format!("unknown key '{key}', did you mean '{}'?", suggestion.trim_start_matches('-')),
+                    None => format!("unknown key '{key}', the keys are the flags of 'tmplt help generate' without the '--'"),
+                };
+                Err(ParsingError { reason })
+            }
+        }
+    }
+
+    /// the spec of the flag and the value passed with it (`--jobs=4` or `-j4`), the flags of
+    /// the command are searched first and then the global ones
+    fn find_flag(
+        arg: &str,
+        specs: &'static [FlagSpec],
+    ) -> Result<(&'static FlagSpec, Option<String>), ParsingError> {
+        let all = || specs.iter().chain(GLOBAL_FLAGS.iter());
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg, None),
+        };
+
+        if let Some(spec) = all().find(|spec| spec.matches(name)) {
+            return Ok((spec, value));
+        }
+
+        // a short flag with its value stuck to it, i.e `-j4`
+        if !name.starts_with("--") && value.is_none() && arg.len() > 2 {
+            let (short, value) = arg.split_at(2);
+            if let Some(spec) = all().find(|spec| spec.value.is_some() && spec.matches(short)) {
+                return Ok((spec, Some(value.to_string())));
+            }
+        }
+
+        let names = all()
+            .flat_map(|spec| std::iter::once(spec.name).chain(spec.aliases.iter().copied()))
+            .collect::<Vec<&str>>();
+        let reason = match suggest(name, &names) {
+            Some(suggestion) => format!("unknown flag '{name}', did you mean '{suggestion}'?"),
+            None if specs.is_empty() => format!("unknown flag '{name}', flags go after the command, i.e 'tmplt gen file.tmplt {name}'"),
+            None => format!("unknown flag '{name}', to check all the avaliable flags use 'tmplt help <command>'"),
+        };
+        Err(ParsingError { reason })
+    }
+
+    fn unknown_command(name: &str) -> ParsingError {
+        let names = COMMANDS
+            .iter()
+            .flat_map(|command| {
+                std::iter::once(command.name).chain(command.aliases.iter().copied())
+            })
+            .collect::<Vec<&str>>();
+
+        let reason = match suggest(name, &names) {
+            Some(suggestion) => format!("unknown command '{name}', did you mean '{suggestion}'?"),
+            None => format!("unknown command '{name}', use tmplt help to get more info"),
+        };
+        ParsingError { reason }
+    }
+
+    /// the closest name to the misspelled one, if it is close enough to be what was meant
+    pub fn suggest<'a>(name: &str, names: &[&'a str]) -> Option<&'a str> {
+        let name = name.trim_start_matches('-');
+        names
+            .iter()
+            .map(|candidate| {
+                (
+                    edit_distance(name, candidate.trim_start_matches('-')),
+                    *candidate,
+                )
+            })
+            .filter(|(distance, candidate)| {
+                *distance <= 2.max(candidate.len() / 4) && *distance < name.len()
+            })
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate)
+    }
+
+    /// the number of characters to insert, remove or replace to go from one word to the other
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let b = b.chars().collect::<Vec<char>>();
+        let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+
+        for (i, ca) in a.chars().enumerate() {
+            let mut current = vec![i + 1];
+            for (j, cb) in b.iter().enumerate() {
+                let replace = previous[j] + usize::from(ca != *cb);
+                current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+            }
+            previous = current;
+        }
+        previous[b.len()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::args::*;
+
+    /// the options of `tmplt <args>`
+    fn parse(args: &[&str]) -> Result<Options, ParsingError> {
+        let args = std::iter::once("tmplt")
+            .chain(args.iter().copied())
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>();
+        ArgsParser::new(args).parse_options(&[])
+    }
+
+    #[test]
+    fn durations_have_a_unit() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("60s"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn a_duration_without_a_unit_is_in_seconds() {
+        assert_eq!(parse_duration("5"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_duration("0"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn invalid_durations_are_rejected() {
+        for value in ["", "s", "1d", "1.5s", "-1s", "1 s", "ten"] {
+            assert_eq!(parse_duration(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn too_long_durations_are_rejected() {
+        assert_eq!(parse_duration(&format!("{}h", u64::MAX / 60)), None);
+        assert_eq!(parse_duration(&format!("{}m", u64::MAX)), None);
+        assert_eq!(parse_duration("99999999999999999999s"), None);
+        assert_eq!(
+            parse_duration(&format!("{}s", u64::MAX)),
+            Some(Duration::from_secs(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn a_value_follows_the_flag_or_its_equal_sign() {
+        for args in [
+            ["gen", "api", "--report", "out.json"],
+            ["gen", "api", "--report=out.json", "--"],
+        ] {
+            let options = parse(&args).unwrap();
+            assert_eq!(options.get_file().as_deref(), Some("api"));
+            assert_eq!(options.get_report_flag().as_deref(), Some("out.json"));
+        }
+        assert!(parse(&["gen", "api", "--report"])
+            .unwrap_err()
+            .to_string()
+            .contains("should be followed by a value"));
+    }
+
+    #[test]
+    fn a_short_flag_takes_its_value_stuck_to_it_or_after_it() {
+        assert_eq!(parse(&["gen", "api", "-j4"]).unwrap().get_jobs_flag(), 4);
+        assert_eq!(
+            parse(&["gen", "api", "-j", "4"]).unwrap().get_jobs_flag(),
+            4
+        );
+        assert_eq!(
+            parse(&["gen", "api", "--jobs=4"]).unwrap().get_jobs_flag(),
+            4
+        );
+        assert!(parse(&["gen", "api", "-j0"]).is_err());
+    }
+
+    #[test]
+    fn everything_after_a_double_dash_is_an_argument() {
+        let options = parse(&["check", "--", "--deny-warnings", "a.tmplt"]).unwrap();
+        assert_eq!(options.get_arguments(), &vec!["--deny-warnings", "a.tmplt"]);
+        assert!(!options.get_deny_warnings_flag());
+    }
+
+    #[test]
+    fn vars_can_be_passed_more_than_once() {
+        let options =
+            parse(&["gen", "api", "--var", "a=1", "--var=b=x=y", "--var", "a=2"]).unwrap();
+        assert_eq!(
+            options.get_vars_flag(),
+            &vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "x=y".to_string()),
+                ("a".to_string(), "2".to_string()),
+            ]
+        );
+        assert!(parse(&["gen", "api", "--var", "=1"]).is_err());
+    }
+
+    #[test]
+    fn global_flags_can_go_before_the_command() {
+        let options = parse(&["--help", "gen"]).unwrap();
+        assert!(matches!(options.get_command(), CommandLineArgs::Help));
+        assert_eq!(options.get_help_topic(), Some("generate"));
+
+        assert!(*parse(&["-V"]).unwrap().get_version_flag());
+    }
+
+    #[test]
+    fn the_flags_of_a_command_go_after_it() {
+        let reason = parse(&["--raw-output", "gen", "api"])
+            .unwrap_err()
+            .to_string();
+        assert!(reason.contains("flags go after the command"), "{reason}");
+    }
+
+    #[test]
+    fn unknown_flags_and_commands_get_a_suggestion() {
+        let reason = parse(&["gen", "api", "--jbos=2"]).unwrap_err().to_string();
+        assert!(reason.contains("did you mean '--jobs'?"), "{reason}");
+
+        let reason = parse(&["generat", "api"]).unwrap_err().to_string();
+        assert!(reason.contains("did you mean 'generate'?"), "{reason}");
+
+        let reason = parse(&["gen", "api", "--zzzzzzzz"])
+            .unwrap_err()
+            .to_string();
+        assert!(!reason.contains("did you mean"), "{reason}");
+    }
+
+    #[test]
+    fn config_keys_are_the_flags_of_generate() {
+        assert_eq!(config_key("batch_size").unwrap().name, "--batch-size");
+        assert_eq!(config_key("log-retention").unwrap().name, "--log-retention");
+        // an alias is a key too
+        assert_eq!(config_key("task-num").unwrap().name, "--batch-size");
+        assert_eq!(config_key("j").unwrap().name, "--jobs");
+
+        assert!(config_key("var").is_err());
+        assert!(config_key("help").is_err());
+        let reason = config_key("save-log").unwrap_err().to_string();
+        assert!(reason.contains("did you mean 'save-logs'?"), "{reason}");
+    }
+
+    #[test]
+    fn a_leading_tilde_is_the_home_directory() {
+        let home = std::path::PathBuf::from(std::env::var_os("HOME").unwrap());
+        assert_eq!(expand_home("~/templates"), home.join("templates"));
+        assert_eq!(expand_home("~"), home);
+        assert_eq!(expand_home("~user/x"), std::path::PathBuf::from("~user/x"));
+        assert_eq!(expand_home("a/~/b"), std::path::PathBuf::from("a/~/b"));
+
+        let options = parse(&["list", "--template-path=~/templates"]).unwrap();
+        assert_eq!(options.get_template_paths_flag()[0], home.join("templates"));
+    }
 }
@@ -0,0 +1,404 @@
+//! `tmplt check`, reads the templates like `tmplt gen` does but runs nothing. every error is
+//! reported (not only the first one) along with warnings about things that are valid but likely
+//! mistakes, i.e a variable that is never used or a path declared twice.
+//!
+//! it exits with 0 when no error is found, 1 when one is (or a warning with `--deny-warnings`)
+//! and 2 when a template can't be found, so it can be used as a pre-commit hook.
+
+use std::path::{Path, PathBuf};
+
+use crate::arg_parser::args::Options;
+use crate::core::Tokens::SectionIdentity;
+use crate::core::{
+    self, path_components, ExecutableCommand, Interpreter, Metadata, Problem, Severity, SourceLine,
+    Tree,
+};
+use crate::logger::console::{self, Color, Stream};
+use crate::logger::writer::LogStatus;
+use crate::logprint;
+use crate::templates;
+
+/// checks the template, the problems are sorted by line (the ones without a line first)
+pub fn check_template(path: &Path) -> Vec<Problem> {
+    match core::Parser::new(templates::EXTENSION.to_string()).parse_file(path.display().to_string())
+    {
+        Ok(lines) => check_lines(lines),
+        Err(e) => vec![Problem::error(None, e.to_string())],
+    }
+}
+
+fn check_lines(lines: Vec<SourceLine>) -> Vec<Problem> {
+    let (tree, syntax_errors) = Tree::construct_all(lines);
+    let mut problems = syntax_errors
+        .iter()
+        .map(|e| Problem::error(Some(e.line()), e.reason().to_string()))
+        .collect::<Vec<Problem>>();
+
+    if let Err(e) = Metadata::from_tree(&tree) {
+        problems.push(Problem::error(None, e.to_string()))
+    }
+
+    let mut interpreter = core::construct_interpreter(tree.clone());
+    for e in interpreter.interpret_all() {
+        problems.push(Problem::error(e.line(), e.describe()))
+    }
+
+    unused_variables(&tree, &mut problems);
+    duplicate_paths(&interpreter, &mut problems);
+    undeclared_folders(&interpreter, &mut problems);
+    copies_outside(&interpreter, &mut problems);
+    needless_defers(&interpreter, &mut problems);
+
+    problems.sort_by_key(|problem| problem.line.unwrap_or(0));
+    problems
+}
+
+/// the variables of `__VAR` that no other section uses
+fn unused_variables(tree: &Tree, problems: &mut Vec<Problem>) {
+    let mut declared: Vec<(String, usize)> = vec![];
+    let mut used: Vec<String> = vec![];
+
+    for branch in tree.branches.iter() {
+        for node in branch.nodes.iter() {
+            let words = node.get_words();
+            let declared_words = words.iter().filter(|word| !word.is_empty()).count();
+            match branch.section_kind {
+                SectionIdentity::Variables if declared_words == 3 => {
+                    declared.push((words[0].clone(), node.line))
+                }
+                SectionIdentity::Variables | SectionIdentity::Metadata => (),
+                _ => {
+                    for word in words.iter().chain(node.scope.iter()) {
+                        used.extend(referenced_variables(word))
+                    }
+                }
+            }
+        }
+    }
+
+    for (name, line) in declared {
+        if !used.contains(&name) {
+            problems.push(Problem::warning(
+                Some(line),
+                format!("the variable '{name}' is never used"),
+            ))
+        }
+    }
+}
+
+/// the names of the variables used in the text, `#out/#name` uses `out` and `name` and `##fff` uses nothing
+fn referenced_variables(text: &str) -> Vec<String> {
+    // `##` is a literal `#`
+    text.replace("##", "")
+        .split('#')
+        .skip(1)
+        .map(|after| {
+            after
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect::<String>()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// a path of `__CWD` declared more than once, the second one fails since the path already exists
+fn duplicate_paths(interpreter: &Interpreter, problems: &mut Vec<Problem>) {
+    let actions = interpreter.fs_actions();
+
+    for (i, action) in actions.iter().enumerate() {
+        let path = path_components(action.path());
+        let first = actions[..i]
+            .iter()
+            .find(|other| path_components(other.path()) == path);
+        if let Some(first) = first {
+            problems.push(Problem::warning(
+                action.line(),
+                format!(
+                    "'{}' is already declared at line {}",
+                    action.path(),
+                    first.line().unwrap_or_default()
+                ),
+            ))
+        }
+    }
+}
+
+/// a file or a folder of `__CWD` inside a folder that isn't declared, it can only be created
+/// if the folder already exists
+fn undeclared_folders(interpreter: &Interpreter, problems: &mut Vec<Problem>) {
+    let folders = declared_folders(interpreter);
+
+    for action in interpreter.fs_actions() {
+        let path = path_components(action.path());
+        if path.len() < 2 {
+            continue;
+        }
+
+        let parent = &path[..path.len() - 1];
+        // the root and the directories above the current one always exist
+        let always_exists = parent.iter().all(|c| c == "/" || c == "..");
+        if !always_exists && !folders.iter().any(|folder| folder == parent) {
+            problems.push(Problem::warning(
+                action.line(),
+                format!(
+                    "'{}' is inside '{}/' which isn't declared with FOLDER, it has to exist before the template runs",
+                    action.path(),
+                    display_path(parent)
+                ),
+            ))
+        }
+    }
+}
+
+/// a `COPY_INTO` writing outside of the directory the template generates in, unless it is
+/// into a folder the template declares
+fn copies_outside(interpreter: &Interpreter, problems: &mut Vec<Problem>) {
+    let folders = declared_folders(interpreter);
+
+    for command in interpreter.commands().iter().filter(|c| c.demo) {
+        let dest = match command.args().last() {
+            Some(dest) => dest,
+            None => continue,
+        };
+
+        let path = path_components(dest);
+        let escapes = Path::new(dest).is_absolute()
+            || path
+                .first()
+                .is_some_and(|first| first == "/" || first == "..");
+        let in_folder = folders
+            .iter()
+            .any(|folder| !folder.is_empty() && path.starts_with(folder));
+
+        if escapes && !in_folder {
+            problems.push(Problem::warning(
+                command.line,
+                format!("the copy writes to '{dest}', outside of the directory the template generates in"),
+            ))
+        }
+    }
+}
+
+/// a `DEFER` line that doesn't need to wait, nothing it uses is created by the template
+fn needless_defers(interpreter: &Interpreter, problems: &mut Vec<Problem>) {
+    let folders = declared_folders(interpreter);
+    let inside_folder = |path: &str| {
+        let path = path_components(path);
+        folders
+            .iter()
+            .any(|folder| path.len() > folder.len() && path.starts_with(folder))
+    };
+    // a deferred copy may copy what the scripts made
+    let has_scripts = interpreter.commands().iter().any(|c| !c.demo && !c.defered);
+
+    for action in interpreter.fs_actions().iter().filter(|a| a.2) {
+        if !inside_folder(action.path()) {
+            problems.push(Problem::warning(
+                action.line(),
+                "this line is deferred but doesn't need anything the template creates, DEFER can be removed".to_string(),
+            ))
+        }
+    }
+    for command in interpreter
+        .commands()
+        .iter()
+        .filter(|c| c.demo && c.defered)
+    {
+        let dest = command.args().last().map(|dest| dest.as_str());
+        let uses_folder = command.args().iter().any(|path| inside_folder(path))
+            || dest.is_some_and(|dest| folders.contains(&path_components(dest)));
+        if !uses_folder && !has_scripts {
+            problems.push(Problem::warning(
+                command.line,
+                "this copy is deferred but doesn't need anything the template creates, DEFER can be removed".to_string(),
+            ))
+        }
+    }
+}
+
+/// the components back into a path, `["/", "lib"]` is `/lib`
+fn display_path(components: &[String]) -> String {
+    match components.split_first() {
+        Some((root, rest)) if root == "/" => format!("/{}", rest.join("/")),
+        _ => components.join("/"),
+    }
+}
+
+/// the folders of `__CWD`, as path components
+fn declared_folders(interpreter: &Interpreter) -> Vec<Vec<String>> {
+    interpreter
+        .fs_actions()
+        .iter()
+        .filter(|action| !action.is_file())
+        .map(|action| path_components(action.path()))
+        .collect()
+}
+
+/// `tmplt check [templates...]`, returns if the templates passed the check,
+/// an error means a template couldn't be found
+pub fn handle_check_command(args: &Options) -> Result<bool, String> {
+    let names = args.get_arguments();
+    let paths = match names.is_empty() {
+        true => templates::list_templates(args)
+            .into_iter()
+            .map(|template| template.path)
+            .collect::<Vec<PathBuf>>(),
+        false => names
+            .iter()
+            .map(|name| templates::resolve(name, args))
+            .collect::<Result<Vec<PathBuf>, String>>()?,
+    };
+    if paths.is_empty() {
+        return Err(
+            "no template to check, pass the templates or add some to the search path (see tmplt list)"
+                .to_string(),
+        );
+    }
+
+    let (mut errors, mut warnings) = (0, 0);
+    for path in paths.iter() {
+        for problem in check_template(path) {
+            let label = match problem.severity {
+                Severity::Error => {
+                    errors += 1;
+                    console::paint(Stream::Stdout, "error", Color::Red)
+                }
+                Severity::Warning => {
+                    warnings += 1;
+                    console::paint(Stream::Stdout, "warning", Color::Yellow)
+                }
+            };
+            let location = match problem.line {
+                Some(line) => format!("{}:{line}", path.display()),
+                None => path.display().to_string(),
+            };
+            console::write_line(
+                Stream::Stdout,
+                &format!("{location}: {label}: {}", problem.message),
+            )
+        }
+    }
+
+    logprint!(
+        LogStatus::Info,
+        "checked {} template(s): {errors} error(s), {warnings} warning(s)",
+        paths.len()
+    );
+    Ok(errors == 0 && (warnings == 0 || !*args.get_deny_warnings_flag()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(data: &str) -> Vec<Problem> {
+        let lines = core::Parser::new(templates::EXTENSION.to_string())
+            .parse_data(data.to_string())
+            .unwrap();
+        check_lines(lines)
+    }
+
+    /// the warnings of the template, as `line: message`
+    fn warnings(data: &str) -> Vec<String> {
+        problems(data)
+            .into_iter()
+            .map(|problem| {
+                assert!(
+                    matches!(problem.severity, Severity::Warning),
+                    "{}",
+                    problem.message
+                );
+                format!("{}: {}", problem.line.unwrap_or(0), problem.message)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_formatted_template_has_no_warning() {
+        let data = "__VAR:\n    out = api\n__CWD:\n    FOLDER #out/\n    FILE #out/main.go\n__DEMO:\n    README.md COPY_INTO #out/\n";
+        assert_eq!(warnings(data), Vec::<String>::new());
+    }
+
+    #[test]
+    fn unused_variables_are_reported() {
+        let data = "__VAR:\n    out = api\n    color = ##fff\n__SCRIPTS:\n    echo #out ##color\n";
+        assert_eq!(warnings(data), ["3: the variable 'color' is never used"]);
+    }
+
+    #[test]
+    fn paths_declared_twice_are_reported() {
+        let data = "__CWD:\n    FOLDER bin/\n    FOLDER ./bin\n    FOLDER x/../bin\n";
+        assert_eq!(
+            warnings(data),
+            [
+                "3: './bin' is already declared at line 2",
+                "4: 'x/../bin' is already declared at line 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_inside_undeclared_folders_are_reported() {
+        let data = "__CWD:\n    FOLDER src/\n    FILE src/main.rs\n    FILE lib/mod.rs\n    FILE /lib/mod.rs\n    FILE ../notes.txt\n    FILE /notes.txt\n";
+        assert_eq!(
+            warnings(data),
+            [
+                "4: 'lib/mod.rs' is inside 'lib/' which isn't declared with FOLDER, it has to exist before the template runs",
+                "5: '/lib/mod.rs' is inside '/lib/' which isn't declared with FOLDER, it has to exist before the template runs",
+            ]
+        );
+    }
+
+    #[test]
+    fn an_absolute_folder_isnt_the_relative_one() {
+        let data = "__CWD:\n    FOLDER /src\n    FILE src/main.rs\n    FILE /src/main.rs\n";
+        assert_eq!(
+            warnings(data),
+            ["3: 'src/main.rs' is inside 'src/' which isn't declared with FOLDER, it has to exist before the template runs"]
+        );
+    }
+
+    #[test]
+    fn copies_outside_of_the_template_are_reported() {
+        let data = "__CWD:\n    FOLDER /src\n__DEMO:\n    a.txt COPY_INTO ../a.txt\n    a.txt COPY_INTO /etc/a.txt\n    a.txt COPY_INTO /src/a.txt\n    a.txt COPY_INTO x/../a.txt\n";
+        assert_eq!(
+            warnings(data),
+            [
+                "4: the copy writes to '../a.txt', outside of the directory the template generates in",
+                "5: the copy writes to '/etc/a.txt', outside of the directory the template generates in",
+            ]
+        );
+    }
+
+    #[test]
+    fn needless_defers_are_reported() {
+        let data = "__CWD:\n    FOLDER bin/\n    DEFER FILE bin/app\n    DEFER FILE notes.txt\n__DEMO:\n    DEFER a.txt COPY_INTO b.txt\n    DEFER a.txt COPY_INTO bin/\n";
+        assert_eq!(
+            warnings(data),
+            [
+                "4: this line is deferred but doesn't need anything the template creates, DEFER can be removed",
+                "6: this copy is deferred but doesn't need anything the template creates, DEFER can be removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_short_copy_is_an_error_at_its_line() {
+        for (data, missing) in [
+            ("__DEMO:\n  foo\n", "COPY_INTO"),
+            ("__DEMO:\n  DEFER foo\n", "COPY_INTO"),
+            ("__DEMO:\n  foo COPY_INTO\n", "the destination"),
+            ("__DEMO:\n  DEFER foo COPY_INTO\n", "the destination"),
+        ] {
+            let problems = problems(data);
+            assert_eq!(problems.len(), 1, "{data}");
+            assert!(matches!(problems[0].severity, Severity::Error));
+            assert_eq!(problems[0].line, Some(2));
+            assert_eq!(
+                problems[0].message,
+                format!("when interpreting the DEMO sections, {missing} is missing at line 2 in the demo section, a copy is written like 'source COPY_INTO destination'")
+            );
+        }
+    }
+}
@@ -0,0 +1,533 @@
+//! the configuration files, they set the default value of the flags of `tmplt generate`.
+//! the user config is `~/.config/tmplt/config.toml` (`%APPDATA%\tmplt\config.toml` on windows)
+//! and the project config is the closest `.tmpltrc` in the current directory or its parents,
+//! the project config takes precedence over the user one and the flags over both.
+//!
+//! both files are flat TOML, one `key = value` per line where the key is the name of a flag
+//! (i.e `batch-size = 20`, `save-logs = true` or `shell = "bash -c"`), `#` starts a comment.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::arg_parser::args::{config_flag, config_key, find_command, Options};
+use crate::logger::console::{self, Stream};
+use crate::logger::writer::LogStatus;
+use crate::logprint;
+
+/// the name of the project config file
+pub const PROJECT_CONFIG: &str = ".tmpltrc";
+
+#[derive(Clone, Debug)]
+pub struct ConfigEntry {
+    pub key: String,
+    /// the value without its quotes
+    pub value: String,
+    pub line: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub entries: Vec<ConfigEntry>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigError {
+    path: PathBuf,
+    line: usize,
+    reason: String,
+}
+
+impl ConfigError {
+    pub fn new(path: &Path, line: usize, reason: &str) -> Self {
+        ConfigError {
+            path: path.to_path_buf(),
+            line,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "in the config file {} at line {}, {}",
+            self.path.display(),
+            self.line,
+            self.reason
+        )
+    }
+}
+
+/// `$TMPLT_CONFIG` if set, otherwise `$XDG_CONFIG_HOME/tmplt/config.toml` (`~/.config/tmplt/config.toml`)
+/// or `%APPDATA%\tmplt\config.toml` on windows
+pub fn user_config_path() -> Option<PathBuf> {
+    let env_dir = |key: &str| {
+        env::var_os(key)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+
+    if let Some(path) = env_dir("TMPLT_CONFIG") {
+        return Some(path);
+    }
+
+    let dir = if cfg!(target_os = "windows") {
+        env_dir("APPDATA")
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+    };
+    dir.map(|dir| dir.join("tmplt").join("config.toml"))
+}
+
+/// the closest `.tmpltrc` in the current directory or in one of its parents
+pub fn project_config_path() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG))
+        .find(|path| path.is_file())
+}
+
+/// the config files that exist, the user config first so the project config overrides it
+pub fn load_files() -> Result<Vec<ConfigFile>, ConfigError> {
+    [user_config_path(), project_config_path()]
+        .into_iter()
+        .flatten()
+        .filter(|path| path.is_file())
+        .map(|path| ConfigFile::read(&path))
+        .collect()
+}
+
+/// the name of the key as written in the flags, `batch_size` and `batch-size` are the same key
+pub fn normalize_key(key: &str) -> String {
+    key.trim().replace('_', "-")
+}
+
+/// the name of the flag the key sets, `j`, `task-num` and `batch_size` are `jobs`, `batch-size`
+/// and `batch-size`, an unknown key is only normalized
+pub fn canonical_key(key: &str) -> String {
+    match config_key(key) {
+        Ok(spec) => spec.name.trim_start_matches('-').to_string(),
+        Err(_) => normalize_key(key),
+    }
+}
+
+impl ConfigFile {
+    pub fn read(path: &Path) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| ConfigError::new(path, 0, &format!("unable to read it: {e}")))?;
+
+        let mut entries: Vec<ConfigEntry> = vec![];
+        for (i, line) in content.lines().enumerate() {
+            let line_number = i + 1;
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(ConfigError::new(
+                    path,
+                    line_number,
+                    "tables aren't supported, the config is a list of 'key = value'",
+                ));
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => (key, value),
+                _ => {
+                    return Err(ConfigError::new(
+                        path,
+                        line_number,
+                        "expected 'key = value'",
+                    ))
+                }
+            };
+            let value = parse_value(value.trim())
+                .map_err(|reason| ConfigError::new(path, line_number, &reason))?;
+
+            entries.push(ConfigEntry {
+                key: canonical_key(key),
+                value,
+                line: line_number,
+            })
+        }
+
+        Ok(ConfigFile {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// the value of the key, the last one if the key is set more than once
+    pub fn get(&self, key: &str) -> Option<&ConfigEntry> {
+        let key = canonical_key(key);
+        self.entries.iter().rev().find(|entry| entry.key == key)
+    }
+}
+
+/// sets the key in the config file, the line of the key is replaced (the others are kept as they are)
+/// and the file is created if it doesn't exist
+pub fn set_value(path: &Path, key: &str, value: &str) -> io::Result<()> {
+    let key = canonical_key(key);
+    let line = format!("{key} = {}", format_value(value));
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    let mut replaced = false;
+    let mut lines = content
+        .lines()
+        .filter_map(|current| {
+            let current_key = strip_comment(current)
+                .split_once('=')
+                .map(|(k, _)| canonical_key(k));
+            match current_key {
+                // the other spellings of the key (i.e `j` for `jobs`) are removed so they don't override it
+                Some(current_key) if current_key == key && replaced => None,
+                Some(current_key) if current_key == key => {
+                    replaced = true;
+                    Some(line.clone())
+                }
+                _ => Some(current.to_string()),
+            }
+        })
+        .collect::<Vec<String>>();
+    if !replaced {
+        lines.push(line);
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, lines.join("\n") + "\n")
+}
+
+/// the line without its comment, a `#` inside a string isn't a comment
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if in_string && !escaped => {
+                escaped = true;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => (),
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// a quoted string, a number or a boolean, the value is given back without its quotes
+fn parse_value(raw: &str) -> Result<String, String> {
+    if let Some(quoted) = raw.strip_prefix('"') {
+        let inner = quoted
+            .strip_suffix('"')
+            .ok_or("a string isn't closed with a '\"'")?;
+
+        let mut value = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c @ ('"' | '\\')) => value.push(c),
+                    _ => return Err("unknown escape in a string".to_string()),
+                },
+                '"' => return Err("a '\"' inside a string should be escaped".to_string()),
+                c => value.push(c),
+            }
+        }
+        return Ok(value);
+    }
+
+    let is_number = !raw.is_empty() && raw.chars().all(|c| c.is_ascii_digit());
+    match raw {
+        "true" | "false" => Ok(raw.to_string()),
+        _ if is_number => Ok(raw.to_string()),
+        "" => Err("the value is missing".to_string()),
+        _ => Err(format!("'{raw}' should be quoted, i.e \"{raw}\"")),
+    }
+}
+
+/// the value as written in the file, quoted unless it is a number or a boolean
+fn format_value(value: &str) -> String {
+    let is_number = !value.is_empty() && value.chars().all(|c| c.is_ascii_digit());
+    match value {
+        "true" | "false" => value.to_string(),
+        _ if is_number => value.to_string(),
+        _ => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+/// `tmplt config list`, `tmplt config get <key>` and `tmplt config set <key> <value>`
+pub fn handle_config_command(args: &Options) -> Result<(), String> {
+    let arguments = args.get_arguments();
+    let argument = |i: usize| arguments.get(i).map(|v| v.as_str());
+
+    match (argument(0), argument(1), argument(2)) {
+        (Some("list") | None, None, None) => {
+            let files = load_files().map_err(|e| e.to_string())?;
+            if files.iter().all(|file| file.entries.is_empty()) {
+                logprint!(LogStatus::Info, "no value is set, the config files are:");
+                logprint!(LogStatus::Info, "  user: {}", display(user_config_path()));
+                logprint!(
+                    LogStatus::Info,
+                    "  project: {}",
+                    display(project_config_path())
+                );
+            }
+            for file in files {
+                for entry in file.entries.iter() {
+                    // a value overridden by the project config is listed but marked
+                    let overridden = effective(&entry.key)?
+                        .is_some_and(|(path, line, _)| path != file.path || line != entry.line);
+                    let line = format!(
+                        "{} = {}  ({}:{})",
+                        entry.key,
+                        format_value(&entry.value),
+                        file.path.display(),
+                        entry.line
+                    );
+                    match overridden {
+                        true => console::write_line(Stream::Stdout, &format!("{line} overridden")),
+                        false => console::write_line(Stream::Stdout, &line),
+                    }
+                }
+            }
+            Ok(())
+        }
+        (Some("get"), Some(key), None) => {
+            config_key(key).map_err(|e| e.to_string())?;
+            match effective(key)? {
+                Some((_, _, value)) => {
+                    console::write_line(Stream::Stdout, &value);
+                    Ok(())
+                }
+                None => Err(format!("'{key}' isn't set")),
+            }
+        }
+        (Some("set"), Some(key), Some(value)) => {
+            // the value is checked the same way as the value of the flag
+            config_flag(key, value).map_err(|e| e.to_string())?;
+
+            let path = match *args.get_project_flag() {
+                true => project_config_path()
+                    .or_else(|| env::current_dir().ok().map(|dir| dir.join(PROJECT_CONFIG))),
+                false => user_config_path(),
+            };
+            let path = path.ok_or("unable to find where the config should be saved")?;
+
+            set_value(&path, key, value)
+                .map_err(|e| format!("unable to write to {} : {e}", path.display()))?;
+            logprint!(
+                LogStatus::Info,
+                "{} set to {value} in {}",
+                canonical_key(key),
+                path.display()
+            );
+            Ok(())
+        }
+        _ => Err(format!(
+            "misused the config command, usage: {}",
+            find_command("config").map_or("", |command| command.usage)
+        )),
+    }
+}
+
+/// where the value of the key comes from (the file and the line) and the value,
+/// the project config takes precedence
+fn effective(key: &str) -> Result<Option<(PathBuf, usize, String)>, String> {
+    let files = load_files().map_err(|e| e.to_string())?;
+    Ok(files.iter().rev().find_map(|file| {
+        file.get(key)
+            .map(|entry| (file.path.clone(), entry.line, entry.value.clone()))
+    }))
+}
+
+fn display(path: Option<PathBuf>) -> String {
+    path.map_or("none".to_string(), |path| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::arg_parser::args::ArgsParser;
+
+    /// an empty directory in the temp directory, removed first if it was left by another run
+    fn empty_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("tmplt-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// the options of `tmplt <args>` with the config files in `config`
+    fn parse(args: &[&str], config: &[ConfigFile]) -> Options {
+        let args = std::iter::once("tmplt")
+            .chain(args.iter().copied())
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>();
+        ArgsParser::new(args).parse_options(config).unwrap()
+    }
+
+    #[test]
+    fn a_config_file_is_a_list_of_keys_and_values() {
+        let dir = empty_dir("config-read");
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            "# the defaults\nsave_logs = true\n\nbatch-size = 20 # per batch\nshell = \"bash -c\"\nj = 4\n",
+        )
+        .unwrap();
+
+        let file = ConfigFile::read(&path).unwrap();
+        let entries = file
+            .entries
+            .iter()
+            .map(|entry| (entry.key.as_str(), entry.value.as_str(), entry.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                ("save-logs", "true", 2),
+                ("batch-size", "20", 4),
+                ("shell", "bash -c", 5),
+                ("jobs", "4", 6),
+            ]
+        );
+        // a key is found by any of its spellings
+        assert_eq!(file.get("j").unwrap().value, "4");
+        assert_eq!(file.get("batch_size").unwrap().value, "20");
+        assert!(file.get("timeout").is_none());
+
+        fs::write(&path, "[generate]\njobs = 4\n").unwrap();
+        let reason = ConfigFile::read(&path).unwrap_err().to_string();
+        assert!(
+            reason.contains("at line 1, tables aren't supported"),
+            "{reason}"
+        );
+
+        fs::write(&path, "jobs = 4\nshell\n").unwrap();
+        let reason = ConfigFile::read(&path).unwrap_err().to_string();
+        assert!(
+            reason.contains("at line 2, expected 'key = value'"),
+            "{reason}"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_hash_inside_a_string_isnt_a_comment() {
+        assert_eq!(strip_comment("jobs = 4 # four"), "jobs = 4 ");
+        assert_eq!(
+            strip_comment("shell = \"sh #1\" # a shell"),
+            "shell = \"sh #1\" "
+        );
+        assert_eq!(
+            strip_comment("shell = \"a \\\" # b\""),
+            "shell = \"a \\\" # b\""
+        );
+        assert_eq!(strip_comment("# only a comment"), "");
+    }
+
+    #[test]
+    fn values_are_booleans_numbers_or_quoted_strings() {
+        assert_eq!(parse_value("true"), Ok("true".to_string()));
+        assert_eq!(parse_value("false"), Ok("false".to_string()));
+        assert_eq!(parse_value("20"), Ok("20".to_string()));
+        assert_eq!(parse_value("\"bash -c\""), Ok("bash -c".to_string()));
+        assert_eq!(
+            parse_value("\"a \\\"b\\\"\\n\""),
+            Ok("a \"b\"\n".to_string())
+        );
+        assert_eq!(parse_value("\"\""), Ok(String::new()));
+
+        assert!(parse_value("").is_err());
+        assert!(parse_value("bash")
+            .unwrap_err()
+            .contains("should be quoted"));
+        assert!(parse_value("\"bash").is_err());
+        assert!(parse_value("\"a\"b\"").is_err());
+        assert!(parse_value("\"\\q\"").is_err());
+    }
+
+    #[test]
+    fn setting_a_value_rewrites_its_line() {
+        let dir = empty_dir("config-set");
+        let path = dir.join("tmplt").join("config.toml");
+
+        // the file (and its directory) is created
+        set_value(&path, "jobs", "4").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "jobs = 4\n");
+
+        fs::write(
+            &path,
+            "# the defaults\njobs = 4 # four\n\nshell = \"sh -c\"\nj = 2\n",
+        )
+        .unwrap();
+        set_value(&path, "j", "8").unwrap();
+        set_value(&path, "shell", "bash -c").unwrap();
+        set_value(&path, "save_logs", "true").unwrap();
+        // the comments and the blank lines are kept, the other spelling of `jobs` is removed
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# the defaults\njobs = 8\n\nshell = \"bash -c\"\nsave-logs = true\n"
+        );
+        assert_eq!(
+            ConfigFile::read(&path).unwrap().get("jobs").unwrap().value,
+            "8"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_flags_override_the_project_config_which_overrides_the_user_config() {
+        let dir = empty_dir("config-precedence");
+        let user = dir.join("config.toml");
+        let project = dir.join(PROJECT_CONFIG);
+        fs::write(&user, "batch-size = 20\nheavy-jobs = 3\n").unwrap();
+        fs::write(&project, "batch_size = 30\n").unwrap();
+        let files = [
+            ConfigFile::read(&user).unwrap(),
+            ConfigFile::read(&project).unwrap(),
+        ];
+
+        let options = parse(&["gen", "x.tmplt"], &[]);
+        assert_eq!(*options.get_batch_size_flag(), 10);
+        assert_eq!(options.get_heavy_jobs_flag(), 1);
+
+        let options = parse(&["gen", "x.tmplt"], &files[..1]);
+        assert_eq!(*options.get_batch_size_flag(), 20);
+
+        let options = parse(&["gen", "x.tmplt"], &files);
+        assert_eq!(*options.get_batch_size_flag(), 30);
+        // a key the project config doesn't set comes from the user config
+        assert_eq!(options.get_heavy_jobs_flag(), 3);
+
+        let options = parse(&["gen", "x.tmplt", "--batch-size=40"], &files);
+        assert_eq!(*options.get_batch_size_flag(), 40);
+
+        // only the commands that read the config use it
+        assert_eq!(*parse(&["new", "x"], &files).get_batch_size_flag(), 10);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,784 @@
+/// rewrites a template in the canonical style, used by `tmplt fmt`:
+/// - the sections are written `__NAME :` with their canonical name (`__SCRIPTS` for `__CMD`) and
+///   sorted in the order of the README (`__META`, `__VAR`, `__ENV`, `__CWD`, `__DEMO`, `__SCRIPTS`),
+///   a section declared more than once is merged
+/// - the keywords are written without their aliases (`FOLDER` for `FLDR`, `COPY_INTO` for `COPY`, `IN` for `CD`)
+/// - the blocks are indented with 4 spaces, `{ }` blocks become `:` blocks
+/// - the `=` of the `__VAR` section are aligned
+///
+/// the comments are kept with the line they are written before (or after), and the formatted
+/// template is parsed again to make sure it means the same thing as the original one
+pub mod formatter {
+    use std::fmt;
+
+    use crate::core::parser::parser::strip_comments;
+    use crate::core::Tokens::{SectionIdentity, Token};
+    use crate::core::{Node, Parser, SourceLine, Tree};
+
+    const INDENT: &str = "    ";
+
+    /// the order of the sections once formatted, the one recommended by the README
+    const SECTIONS_ORDER: [SectionIdentity; 6] = [
+        SectionIdentity::Metadata,
+        SectionIdentity::Variables,
+        SectionIdentity::Environment,
+        SectionIdentity::CurrentWorkingDirectory,
+        SectionIdentity::Demostration,
+        SectionIdentity::Scripts,
+    ];
+
+    #[derive(Clone, Debug)]
+    pub struct FormatError {
+        line: Option<usize>,
+        reason: String,
+    }
+
+    impl FormatError {
+        pub fn new(line: Option<usize>, reason: &str) -> Self {
+            FormatError {
+                line,
+                reason: reason.to_string(),
+            }
+        }
+    }
+
+    impl fmt::Display for FormatError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.line {
+                Some(line) => write!(f, "unable to format line {line}: {}", self.reason),
+                None => write!(f, "unable to format the template: {}", self.reason),
+            }
+        }
+    }
+
+    /// a line of the comment preserving tree
+    #[derive(Clone, Debug)]
+    enum Item {
+        /// a logical line, the physical lines it is written over and the comment following it
+        Line {
+            segments: Vec<String>,
+            comment: Option<String>,
+        },
+        /// an `IN dir` block
+        Block {
+            dir: String,
+            comment: Option<String>,
+            items: Vec<Item>,
+        },
+        Comment(Comment),
+        Blank,
+    }
+
+    #[derive(Clone, Debug)]
+    struct Comment {
+        text: String,
+        /// a line inside a `/* */` comment, written as it is
+        verbatim: bool,
+    }
+
+    #[derive(Clone, Debug)]
+    struct Section {
+        kind: SectionIdentity,
+        /// the comments written right before the section
+        leading: Vec<Item>,
+        comment: Option<String>,
+        items: Vec<Item>,
+    }
+
+    /// a section or a block that isn't closed yet
+    #[derive(Clone, Debug)]
+    enum Frame {
+        Section(Section),
+        /// the dir, the indentation of the header, if it is closed with `}` and the comment
+        Block(String, usize, bool, Option<String>, Vec<Item>),
+    }
+
+    impl Frame {
+        fn items(&mut self) -> &mut Vec<Item> {
+            match self {
+                Frame::Section(section) => &mut section.items,
+                Frame::Block(_, _, _, _, items) => items,
+            }
+        }
+    }
+
+    /// builds the tree from the logical lines, the comments and the blank lines are pending until
+    /// the next line tells where they belong
+    #[derive(Default)]
+    struct Builder {
+        header: Vec<Item>,
+        /// the first section if the header is written right before it, without a blank line
+        attached: Option<SectionIdentity>,
+        sections: Vec<Section>,
+        stack: Vec<Frame>,
+    }
+
+    impl Builder {
+        fn close_top(&mut self) {
+            match self.stack.pop() {
+                Some(Frame::Block(dir, _, _, comment, items)) => {
+                    if let Some(parent) = self.stack.last_mut() {
+                        parent.items().push(Item::Block {
+                            dir,
+                            comment,
+                            items,
+                        })
+                    }
+                }
+                Some(Frame::Section(section)) => self.sections.push(section),
+                None => (),
+            }
+        }
+
+        /// closes the `:` blocks a line indented like this isn't part of, the pending comments
+        /// indented deeper than a block are kept at its end (but not the blank lines after them,
+        /// they separate the block from what follows it)
+        fn close_blocks(&mut self, indent: usize, pending: &mut Vec<(Item, usize)>) {
+            while let Some(Frame::Block(_, header, false, _, items)) = self.stack.last_mut() {
+                if *header < indent {
+                    break;
+                }
+                let mut inside = pending.iter().take_while(|(_, i)| *i > *header).count();
+                while inside > 0 && matches!(pending[inside - 1].0, Item::Blank) {
+                    inside -= 1
+                }
+                items.extend(pending.drain(..inside).map(|(item, _)| item));
+                self.close_top();
+            }
+        }
+
+        /// adds the pending comments to the innermost section or block, or to the header of the file
+        fn flush(&mut self, pending: &mut Vec<(Item, usize)>) {
+            let items = pending.drain(..).map(|(item, _)| item);
+            match self.stack.last_mut() {
+                Some(frame) => frame.items().extend(items),
+                None => self.header.extend(items),
+            }
+        }
+
+        fn open_section(
+            &mut self,
+            line: &SourceLine,
+            comment: Option<String>,
+            pending: &mut Vec<(Item, usize)>,
+        ) {
+            self.close_blocks(0, pending);
+
+            // the comments that aren't indented right before the section are about it, the
+            // comments at the top of the file stay at the top, the first section may be moved
+            // once the sections are sorted
+            let leading = match self.stack.is_empty() {
+                true => {
+                    if pending
+                        .last()
+                        .is_some_and(|(item, _)| !matches!(item, Item::Blank))
+                    {
+                        self.attached = Some(section_kind(line))
+                    }
+                    0
+                }
+                false => pending
+                    .iter()
+                    .rev()
+                    .take_while(|(item, i)| *i == 0 || matches!(item, Item::Blank))
+                    .count(),
+            };
+            let leading = pending.split_off(pending.len() - leading);
+            self.flush(pending);
+            while !self.stack.is_empty() {
+                self.close_top();
+            }
+
+            self.stack.push(Frame::Section(Section {
+                kind: section_kind(line),
+                leading: leading.into_iter().map(|(item, _)| item).collect(),
+                comment,
+                items: vec![],
+            }))
+        }
+
+        /// a `}`, closes the innermost `{` block and the `:` blocks inside it
+        fn close_brace(&mut self, line: &SourceLine, pending: &mut Vec<(Item, usize)>) {
+            self.close_blocks(line.indent, pending);
+            while let Some(Frame::Block(_, _, false, _, _)) = self.stack.last() {
+                self.flush(pending);
+                self.close_top();
+            }
+            self.flush(pending);
+            self.close_top();
+        }
+
+        fn add_line(&mut self, line: &SourceLine, item: Item, pending: &mut Vec<(Item, usize)>) {
+            self.close_blocks(line.indent, pending);
+            self.flush(pending);
+
+            let (comment, segments) = match item {
+                Item::Line { segments, comment } => (comment, segments),
+                item => return self.flush(&mut vec![(item, 0)]),
+            };
+            match scope_header(&line.text) {
+                Some((dir, brace)) => {
+                    self.stack
+                        .push(Frame::Block(dir, line.indent, brace, comment, vec![]))
+                }
+                None => self.flush(&mut vec![(Item::Line { segments, comment }, 0)]),
+            }
+        }
+    }
+
+    /// the section a line like `__NAME :` or `__NAME {` declares
+    fn section_kind(line: &SourceLine) -> SectionIdentity {
+        let name_end = line
+            .text
+            .find(|c: char| c.is_whitespace() || c == ':' || c == '{')
+            .unwrap_or(line.text.len());
+        SectionIdentity::from(line.text[..name_end].to_string())
+    }
+
+    /// `IN dir :`, `IN dir {` or `CD dir :`, same as the syntax tree
+    fn scope_header(text: &str) -> Option<(String, bool)> {
+        let rest = text
+            .strip_prefix("IN ")
+            .or_else(|| text.strip_prefix("CD "))?
+            .trim();
+        let (path, brace) = match (rest.strip_suffix(':'), rest.strip_suffix('{')) {
+            (Some(path), _) => (path.trim(), false),
+            (_, Some(path)) => (path.trim(), true),
+            _ => return None,
+        };
+        match path.is_empty() || path.contains(char::is_whitespace) {
+            true => None,
+            false => Some((path.to_string(), brace)),
+        }
+    }
+
+    /// the end of the `/* */` comment the physical line starts with, its code and its comment,
+    /// a comment in the middle of the line is kept in the code
+    fn split_comment(original: &str, stripped: &str) -> (Option<String>, String, Option<String>) {
+        let code = stripped.trim_end();
+        if original.trim() == code.trim() {
+            return (None, code.trim().to_string(), None);
+        }
+        if let Some(comment) = original.strip_prefix(code) {
+            return (
+                None,
+                code.trim().to_string(),
+                Some(comment.trim().to_string()),
+            );
+        }
+        // i.e `end of the comment */ echo hi`
+        match original.trim_end().strip_suffix(code.trim()) {
+            Some(comment) if comment.trim_end().ends_with("*/") => (
+                Some(comment.trim().to_string()),
+                code.trim().to_string(),
+                None,
+            ),
+            _ => (None, original.trim().to_string(), None),
+        }
+    }
+
+    /// a comment line or a blank line, `None` if the line has code
+    fn comment_line(original: &str, stripped: &str) -> Option<Item> {
+        if !stripped.trim().is_empty() {
+            return None;
+        }
+        let text = original.trim();
+        Some(match text.is_empty() {
+            true => Item::Blank,
+            false => Item::Comment(Comment {
+                verbatim: !text.starts_with("//") && !text.starts_with("/*"),
+                text: match text.starts_with("//") || text.starts_with("/*") {
+                    true => text.to_string(),
+                    false => original.trim_end().to_string(),
+                },
+            }),
+        })
+    }
+
+    fn indentation(line: &str) -> usize {
+        line.chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum()
+    }
+
+    /// the template in the canonical style
+    pub fn format_template(source: &str) -> Result<String, FormatError> {
+        let parser = Parser::new("tmplt".to_string());
+        let io_error = |e: std::io::Error| FormatError::new(None, &e.to_string());
+
+        let logical = parser.parse_data(source.to_string()).map_err(io_error)?;
+        Tree::construct(logical.clone()).map_err(|e| {
+            FormatError::new(Some(e.line()), &format!("{}, see tmplt check", e.reason()))
+        })?;
+
+        let stripped = strip_comments(source).map_err(io_error)?;
+        let originals = source
+            .split('\n')
+            .map(|l| l.trim_end_matches('\r'))
+            .collect::<Vec<&str>>();
+        let strippeds = stripped
+            .split('\n')
+            .map(|l| l.trim_end_matches('\r'))
+            .collect::<Vec<&str>>();
+
+        let mut builder = Builder::default();
+        let mut pending: Vec<(Item, usize)> = vec![];
+        let first = logical
+            .first()
+            .map_or(originals.len(), |line| line.number - 1);
+        collect_pending(&originals, &strippeds, 0..first, &mut pending);
+
+        for (k, line) in logical.iter().enumerate() {
+            let start = line.number - 1;
+            let end = logical
+                .get(k + 1)
+                .map_or(originals.len(), |next| next.number - 1);
+            let code_lines = (start..end)
+                .filter(|i| !strippeds[*i].trim().is_empty())
+                .collect::<Vec<usize>>();
+            let last = *code_lines.last().unwrap_or(&start);
+
+            // the comments between the physical lines of a logical line are moved before it
+            let mut hoisted: Vec<(Item, usize)> = vec![];
+            let mut segments: Vec<String> = vec![];
+            let mut comment: Option<String> = None;
+            for i in start..=last {
+                if let Some(item) = comment_line(originals[i], strippeds[i]) {
+                    if !matches!(item, Item::Blank) {
+                        hoisted.push((item, line.indent))
+                    }
+                    continue;
+                }
+
+                let (comment_end, code, line_comment) = split_comment(originals[i], strippeds[i]);
+                if let Some(text) = comment_end {
+                    let comment = Comment {
+                        text,
+                        verbatim: false,
+                    };
+                    hoisted.push((Item::Comment(comment), line.indent))
+                }
+                let code = match code.strip_suffix('\\') {
+                    Some(rest) if rest.is_empty() || rest.ends_with(char::is_whitespace) => {
+                        rest.trim_end().to_string()
+                    }
+                    _ => code,
+                };
+                if !code.is_empty() {
+                    segments.push(code)
+                }
+                match (i == last, line_comment) {
+                    (true, line_comment) => comment = line_comment,
+                    (false, Some(text)) => hoisted.push((
+                        Item::Comment(Comment {
+                            text,
+                            verbatim: false,
+                        }),
+                        line.indent,
+                    )),
+                    (false, None) => (),
+                }
+            }
+
+            if line.text.starts_with("__") {
+                pending.append(&mut hoisted);
+                builder.open_section(line, comment, &mut pending);
+            } else if line.text == "}" {
+                pending.append(&mut hoisted);
+                builder.close_brace(line, &mut pending);
+                if let Some(text) = comment {
+                    let comment = Comment {
+                        text,
+                        verbatim: false,
+                    };
+                    pending.push((Item::Comment(comment), line.indent))
+                }
+            } else {
+                pending.append(&mut hoisted);
+                builder.add_line(line, Item::Line { segments, comment }, &mut pending);
+            }
+
+            collect_pending(&originals, &strippeds, last + 1..end, &mut pending);
+        }
+        builder.close_blocks(0, &mut pending);
+        builder.flush(&mut pending);
+        while !builder.stack.is_empty() {
+            builder.close_top();
+        }
+
+        let formatted = print(
+            builder.header,
+            builder.attached,
+            merge_sections(builder.sections),
+        );
+
+        // the formatted template has to be the same template, only written differently
+        let reparsed = parser.parse_data(formatted.clone()).map_err(io_error)?;
+        if shape(logical) != shape(reparsed) {
+            return Err(FormatError::new(
+                None,
+                "the formatted template doesn't mean the same thing as the original one, it was left as it is (please report it)",
+            ));
+        }
+
+        Ok(formatted)
+    }
+
+    /// the comments and the blank lines of the physical lines, a blank line gets the indentation
+    /// of what follows it so it stays in the same block
+    fn collect_pending(
+        originals: &[&str],
+        strippeds: &[&str],
+        lines: std::ops::Range<usize>,
+        pending: &mut Vec<(Item, usize)>,
+    ) {
+        for i in lines {
+            let item = match comment_line(originals[i], strippeds[i]) {
+                Some(item) => item,
+                None => continue,
+            };
+            // the lines of a `/* */` comment belong where the comment starts
+            let indent = match &item {
+                Item::Blank => usize::MAX,
+                Item::Comment(comment) if comment.verbatim => {
+                    pending.last().map_or(0, |(_, indent)| *indent)
+                }
+                _ => indentation(originals[i]),
+            };
+            pending.push((item, indent))
+        }
+        let mut next = usize::MAX;
+        for (item, indent) in pending.iter_mut().rev() {
+            match item {
+                Item::Blank => *indent = next,
+                _ => next = *indent,
+            }
+        }
+    }
+
+    /// sorts the sections and merges the ones declared more than once
+    fn merge_sections(mut sections: Vec<Section>) -> Vec<Section> {
+        let rank = |kind: &SectionIdentity| {
+            SECTIONS_ORDER
+                .iter()
+                .position(|k| k == kind)
+                .unwrap_or(SECTIONS_ORDER.len())
+        };
+        sections.sort_by_key(|section| rank(&section.kind));
+
+        let mut merged: Vec<Section> = vec![];
+        for section in sections {
+            match merged.last_mut() {
+                Some(last) if last.kind == section.kind => {
+                    last.items.push(Item::Blank);
+                    last.items.extend(section.leading);
+                    if let Some(text) = section.comment {
+                        last.items.push(Item::Comment(Comment {
+                            text,
+                            verbatim: false,
+                        }))
+                    }
+                    last.items.extend(section.items)
+                }
+                _ => merged.push(section),
+            }
+        }
+        merged
+    }
+
+    /// `attached` is the section the header was written right before, it stays so if the
+    /// section is still the first one
+    fn print(
+        header: Vec<Item>,
+        attached: Option<SectionIdentity>,
+        sections: Vec<Section>,
+    ) -> String {
+        let mut out: Vec<String> = vec![];
+        print_items(&header, 0, SectionIdentity::UnknownSection, &mut out);
+
+        for (i, section) in sections.into_iter().enumerate() {
+            let after_header = i == 0 && attached == Some(section.kind);
+            if !out.is_empty() && !after_header {
+                out.push(String::new())
+            }
+            print_items(&section.leading, 0, section.kind, &mut out);
+            out.push(with_comment(
+                format!("{} :", section.kind.name()),
+                &section.comment,
+            ));
+            print_items(&section.items, 1, section.kind, &mut out);
+        }
+
+        out.join("\n") + "\n"
+    }
+
+    fn print_items(items: &[Item], level: usize, kind: SectionIdentity, out: &mut Vec<String>) {
+        let indent = INDENT.repeat(level);
+        // the width of the names of the variables, so the `=` are aligned
+        let width = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Line { segments, .. } => variable(kind, segments),
+                _ => None,
+            })
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+
+        // no blank line at the start or the end of a block, and never two in a row
+        let mut blank = false;
+        let mut printed = false;
+        for item in items {
+            if let Item::Blank = item {
+                blank = printed;
+                continue;
+            }
+            if blank {
+                out.push(String::new());
+                blank = false;
+            }
+            printed = true;
+
+            match item {
+                Item::Line { segments, comment } => {
+                    if let Some((name, value)) = variable(kind, segments) {
+                        let line = format!("{indent}{name:<width$} = {value}");
+                        out.push(with_comment(line, comment));
+                        continue;
+                    }
+
+                    let last = segments.len().saturating_sub(1);
+                    for (i, segment) in segments.iter().enumerate() {
+                        let line = match i {
+                            0 => format!("{indent}{}", canonical_line(kind, segment)),
+                            _ => format!("{indent}{INDENT}{segment}"),
+                        };
+                        match i == last {
+                            true => out.push(with_comment(line, comment)),
+                            false => out.push(format!("{line} \\")),
+                        }
+                    }
+                }
+                Item::Block {
+                    dir,
+                    comment,
+                    items,
+                } => {
+                    out.push(with_comment(format!("{indent}IN {dir} :"), comment));
+                    print_items(items, level + 1, kind, out);
+                }
+                Item::Comment(comment) => match comment.verbatim {
+                    true => out.push(comment.text.clone()),
+                    false => out.push(format!("{indent}{}", comment.text)),
+                },
+                Item::Blank => (),
+            }
+        }
+    }
+
+    fn with_comment(line: String, comment: &Option<String>) -> String {
+        match comment {
+            Some(comment) => format!("{line} {comment}"),
+            None => line,
+        }
+    }
+
+    /// the name and the value of a line of `__VAR` written on one line
+    fn variable(kind: SectionIdentity, segments: &[String]) -> Option<(String, String)> {
+        if kind != SectionIdentity::Variables || segments.len() != 1 {
+            return None;
+        }
+        let words = segments[0].split_whitespace().collect::<Vec<&str>>();
+        match words.as_slice() {
+            [name, "=", value] => Some((name.to_string(), value.to_string())),
+            _ => None,
+        }
+    }
+
+    /// the line with the aliases of its keywords replaced, only the words that are read as
+    /// keywords are replaced (i.e the `COPY` of `echo COPY` is kept)
+    fn canonical_line(kind: SectionIdentity, text: &str) -> String {
+        let mut words = text
+            .split(' ')
+            .map(|w| w.to_string())
+            .collect::<Vec<String>>();
+        let canonical = |word: &mut String| {
+            if let Some(keyword) = Token::from(word.clone()).keyword() {
+                *word = keyword.to_string()
+            }
+        };
+
+        match kind {
+            SectionIdentity::CurrentWorkingDirectory | SectionIdentity::Demostration => {
+                for word in words.iter_mut() {
+                    if matches!(
+                        Token::from(word.clone()),
+                        Token::DeclareFolder
+                            | Token::DeclareFile
+                            | Token::CopyAction
+                            | Token::DeferAction
+                    ) {
+                        canonical(word)
+                    }
+                }
+            }
+            SectionIdentity::Scripts => {
+                // the modifiers before the command, like the interpreter reads them
+                let mut idx = 0;
+                while idx < words.len() {
+                    let token = Token::from(words[idx].clone());
+                    let skip = match token {
+                        Token::DeferAction | Token::Interactive | Token::Heavy => 0,
+                        Token::WorkingDir | Token::Timeout | Token::Backoff | Token::Retry => 1,
+                        Token::SetEnv => words[idx + 1..]
+                            .iter()
+                            .take_while(|word| {
+                                word.split_once('=').is_some_and(|(key, _)| !key.is_empty())
+                            })
+                            .count(),
+                        _ => break,
+                    };
+                    canonical(&mut words[idx]);
+                    idx += 1 + skip;
+                }
+            }
+            _ => (),
+        }
+
+        words.join(" ")
+    }
+
+    /// what the template means: the lines of every section with their keywords and their `IN` blocks
+    fn shape(lines: Vec<SourceLine>) -> Option<Vec<(usize, Vec<String>)>> {
+        let tree = Tree::construct(lines).ok()?;
+        let node_shape = |kind: SectionIdentity, node: &Node| {
+            let words = node
+                .get_words()
+                .into_iter()
+                .filter(|word| kind != SectionIdentity::Variables || !word.is_empty())
+                .map(|word| match Token::from(word.clone()).keyword() {
+                    Some(keyword) => keyword.to_string(),
+                    None => word,
+                })
+                .collect::<Vec<String>>();
+            format!("{} | {}", node.scope.join(" > "), words.join(" "))
+        };
+
+        let mut branches = tree
+            .branches
+            .iter()
+            .map(|branch| {
+                let rank = SECTIONS_ORDER
+                    .iter()
+                    .position(|kind| *kind == branch.section_kind)
+                    .unwrap_or(SECTIONS_ORDER.len());
+                let nodes = branch
+                    .nodes
+                    .iter()
+                    .map(|node| node_shape(branch.section_kind, node))
+                    .collect::<Vec<String>>();
+                (rank, nodes)
+            })
+            .collect::<Vec<(usize, Vec<String>)>>();
+        branches.sort_by_key(|(rank, _)| *rank);
+        Some(branches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::formatter::*;
+
+    /// the template formatted, checking that formatting it again changes nothing
+    fn fmt(source: &str) -> String {
+        let formatted = format_template(source).unwrap();
+        assert_eq!(
+            format_template(&formatted).unwrap(),
+            formatted,
+            "formatting it twice changed it"
+        );
+        formatted
+    }
+
+    #[test]
+    fn comments_stay_with_their_lines() {
+        let source = "// the file\n\n__CMD:\n    // build it\n    npm i // deps\n// about cwd\n__CWD {\n    FLDR web/ // the site\n}\n";
+        assert_eq!(
+            fmt(source),
+            "// the file\n\n// about cwd\n__CWD :\n    FOLDER web/ // the site\n\n__SCRIPTS :\n    // build it\n    npm i // deps\n"
+        );
+    }
+
+    #[test]
+    fn a_comment_at_the_top_stays_at_the_top() {
+        assert_eq!(
+            fmt("// scripts first\n__SCRIPTS:\n    ls\n__VAR:\n    x = 1\n"),
+            "// scripts first\n\n__VAR :\n    x = 1\n\n__SCRIPTS :\n    ls\n"
+        );
+        // unless its section is still the first one
+        assert_eq!(
+            fmt("// the vars\n__VAR:\n    x = 1\n__SCRIPTS:\n    ls\n"),
+            "// the vars\n__VAR :\n    x = 1\n\n__SCRIPTS :\n    ls\n"
+        );
+    }
+
+    #[test]
+    fn brace_blocks_become_colon_blocks() {
+        let source = "__SCRIPTS {\n    CD web {\n        npm i\n      IN sub:\n          ls\n    }\n    echo done\n}\n";
+        assert_eq!(
+            fmt(source),
+            "__SCRIPTS :\n    IN web :\n        npm i\n        IN sub :\n            ls\n    echo done\n"
+        );
+    }
+
+    #[test]
+    fn block_comments_are_kept_as_they_are() {
+        let source = "/* a\n   template */\n__SCRIPTS:\n    echo one /* inline */ two\n    /* about\n   ls */\n    ls\n";
+        assert_eq!(
+            fmt(source),
+            "/* a\n   template */\n__SCRIPTS :\n    echo one /* inline */ two\n    /* about\n   ls */\n    ls\n"
+        );
+    }
+
+    #[test]
+    fn sections_declared_twice_are_merged() {
+        let source =
+            "__CWD:\n    IN a:\n        FLDR x\n__SCRIPTS:\n    ls\n__CWD:\n    // b\n    FLDR b\n";
+        assert_eq!(
+            fmt(source),
+            "__CWD :\n    IN a :\n        FOLDER x\n\n    // b\n    FOLDER b\n\n__SCRIPTS :\n    ls\n"
+        );
+        assert_eq!(
+            fmt("__SCRIPTS:\n    IN a {\n        ls\n    }\n__SCRIPTS:\n    ls\n"),
+            "__SCRIPTS :\n    IN a :\n        ls\n\n    ls\n"
+        );
+    }
+
+    #[test]
+    fn the_equal_signs_are_aligned() {
+        assert_eq!(
+            fmt("__VAR :\n    name = hello\n    longer_name   =   #name/x\n\n    x = 1\n"),
+            "__VAR :\n    name        = hello\n    longer_name = #name/x\n\n    x           = 1\n"
+        );
+    }
+
+    #[test]
+    fn long_lines_keep_their_backslashes() {
+        assert_eq!(
+            fmt("__CMD:\n    npm install \\\n      --save   foo // deps\n"),
+            "__SCRIPTS :\n    npm install \\\n        --save   foo // deps\n"
+        );
+    }
+
+    #[test]
+    fn a_formatted_template_is_left_as_it_is() {
+        let formatted = "// my template\n\n__META :\n    name = demo\n\n__VAR :\n    a   = 1\n    bcd = 2\n\n__CWD :\n    FOLDER a/\n    IN a/ :\n        FILE main.go // main\n\n        // more\n        FOLDER x/\n\n/* copied */\n__DEMO :\n    README.md COPY_INTO a/\n\n__SCRIPTS :\n    DEFER IN a/ ENV A=1 go build\n";
+        assert_eq!(fmt(formatted), formatted);
+    }
+
+    #[test]
+    fn an_invalid_template_isnt_formatted() {
+        assert!(format_template("    ls\n__SCRIPTS:\n").is_err());
+    }
+}
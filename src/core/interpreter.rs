@@ -1,14 +1,25 @@
 pub mod interpreter {
     use std::collections::HashMap;
+    use std::collections::VecDeque;
     use std::fmt::{Debug, Display};
-    use std::process::Command;
+    use std::io::{self, BufRead, BufReader, Read};
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
     use std::{fmt, fs};
 
+    use crate::arg_parser::args::{parse_duration, ConflictPolicy, Options};
     use crate::core::Tokens::{SectionIdentity, Token};
-    use crate::core::{Branch, Tree};
-    use crate::logformat;
-    use crate::logger::writer::{LogStatus, LogWriter};
-    use crate::tasks::{OpArcMutex, Task, TaskSchedular, TasksExecutor};
+    use crate::core::{Branch, Node, Tree};
+    use crate::events;
+    use crate::logger::console::{self, Color, Stream};
+    use crate::logger::writer::{log_enabled, LogStatus, LogWriter};
+    use crate::logprint;
+    use crate::tasks::{
+        is_cancelled, register_child, sleep_unless_cancelled, terminate_child, unregister_child,
+        OpArcMutex, ResourceClass, Task, TaskContext, TaskSchedular, TasksExecutor,
+    };
 
     #[derive(Clone, Debug)]
     pub enum BufferType {
@@ -21,8 +32,12 @@ pub mod interpreter {
     pub struct Interpreter {
         /// hash map of format (var_name : var_value)
         _variable_buf: HashMap<String, String>,
+        /// the environment variables of the `__ENV` section in the order they are declared
+        _env_buf: Vec<(String, String)>,
         _commands_buf: Vec<CommandSerializer>,
         _fs_actions_buf: Vec<ExacutableFsAction>,
+        /// the variables passed with `--var`, they replace the ones of the `__VAR` section
+        _variable_overrides: Vec<(String, String)>,
 
         pub syntax_tree: Tree,
     }
@@ -32,6 +47,8 @@ pub mod interpreter {
     pub struct InterpreterError {
         when: String,
         reason: String,
+        /// the line that couldn't be interpreted, set by `interpret_all`
+        line: Option<usize>,
     }
 
     impl InterpreterError {
@@ -39,8 +56,18 @@ pub mod interpreter {
             InterpreterError {
                 when: when.to_string(),
                 reason: reason.to_string(),
+                line: None,
             }
         }
+
+        pub fn line(&self) -> Option<usize> {
+            self.line
+        }
+
+        /// the error without the `Interpreter Error:` prefix, i.e `when interpreting variables, ...`
+        pub fn describe(&self) -> String {
+            format!("when {}, {}", self.when, self.reason)
+        }
     }
 
     impl fmt::Debug for InterpreterError {
@@ -53,8 +80,8 @@ pub mod interpreter {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             writeln!(
                 f,
-                " variables buffer : {:?}\n commands buffer : {:?}\n FileSystem actions : {:?}",
-                self._variable_buf, self._commands_buf, self._fs_actions_buf
+                " variables buffer : {:?}\n environment buffer : {:?}\n commands buffer : {:?}\n FileSystem actions : {:?}",
+                self._variable_buf, self._env_buf, self._commands_buf, self._fs_actions_buf
             )
         }
     }
@@ -63,55 +90,144 @@ pub mod interpreter {
         pub fn construct(syntax_tree: Tree) -> Self {
             Interpreter {
                 _variable_buf: HashMap::new(),
+                _env_buf: vec![],
                 syntax_tree,
                 _commands_buf: vec![],
                 _fs_actions_buf: vec![],
+                _variable_overrides: vec![],
             }
         }
 
+        /// sets variables as if they were declared last in the `__VAR` section
+        pub fn override_variables(&mut self, variables: &[(String, String)]) -> &mut Self {
+            self._variable_overrides = variables.to_vec();
+            self
+        }
+
         /// interpret the syntax tree and store variables in its own buffer and commands in a seperate buffer
         /// and create tasks after serilizing the command and the variables it uses
         pub fn interpret(&mut self) -> Result<&mut Self, InterpreterError> {
+            match self.interpret_all().into_iter().next() {
+                Some(e) => Err(e),
+                None => Ok(self),
+            }
+        }
+
+        /// same as `interpret` but goes on after an error, every line that can't be interpreted
+        /// gives its own error and is left out of the buffers
+        pub fn interpret_all(&mut self) -> Vec<InterpreterError> {
+            let mut errors: Vec<InterpreterError> = vec![];
+            // every line is interpreted on its own so an error doesn't hide the ones after it
+            let lines = |branch: &Branch| {
+                branch
+                    .nodes
+                    .iter()
+                    .map(|node| {
+                        let mut line = branch.clone();
+                        line.nodes = vec![node.clone()];
+                        line
+                    })
+                    .collect::<Vec<Branch>>()
+            };
+
+            let at_line = |line: &Branch| {
+                let number = line.nodes.first().map(|node| node.line);
+                move |mut e: InterpreterError| {
+                    e.line = number;
+                    e
+                }
+            };
+
             for branch in self.syntax_tree.branches.clone() {
                 if branch.section_kind == SectionIdentity::Variables {
-                    self.variable_parsing(branch)?;
+                    for line in lines(&branch) {
+                        let at_line = at_line(&line);
+                        errors.extend(self.variable_parsing(line).map_err(at_line).err());
+                    }
                 }
             }
+            for (name, value) in self._variable_overrides.clone() {
+                self._variable_buf.insert(name, value);
+            }
 
             for branch in self.syntax_tree.branches.clone() {
-                let mut command_serializers: Vec<CommandSerializer> = vec![];
-                let mut fs_actions: Vec<ExacutableFsAction> = vec![];
-                match branch.section_kind {
-                    SectionIdentity::CurrentWorkingDirectory => {
-                        self.dir_structure_parser(branch, &mut fs_actions)?
-                    }
-                    SectionIdentity::Demostration => {
-                        self.demos_parser(branch, &mut command_serializers)?
+                if branch.section_kind == SectionIdentity::Environment {
+                    for line in lines(&branch) {
+                        let at_line = at_line(&line);
+                        errors.extend(self.env_parsing(line).map_err(at_line).err());
                     }
-                    SectionIdentity::Scripts => {
-                        self.scripts_parsing(branch, &mut command_serializers)
+                }
+            }
+
+            for branch in self.syntax_tree.branches.clone() {
+                for line in lines(&branch) {
+                    let at_line = at_line(&line);
+                    let mut command_serializers: Vec<CommandSerializer> = vec![];
+                    let mut fs_actions: Vec<ExacutableFsAction> = vec![];
+                    let result = match branch.section_kind {
+                        SectionIdentity::CurrentWorkingDirectory => {
+                            self.dir_structure_parser(line, &mut fs_actions)
+                        }
+                        SectionIdentity::Demostration => {
+                            self.demos_parser(line, &mut command_serializers)
+                        }
+                        SectionIdentity::Scripts => {
+                            self.scripts_parsing(line, &mut command_serializers)
+                        }
+                        _ => break,
+                    };
+                    if let Err(e) = result {
+                        errors.push(at_line(e));
+                        continue;
                     }
-                    _ => continue,
+                    self._commands_buf.append(&mut command_serializers);
+                    self._fs_actions_buf.append(&mut fs_actions)
                 }
-                self._commands_buf.append(&mut command_serializers);
-                self._fs_actions_buf.append(&mut fs_actions)
             }
             self._variable_buf.clear();
 
-            Ok(self)
+            errors
+        }
+
+        /// the scripts and the `__DEMO` copies, available once the syntax tree is interpreted
+        pub fn commands(&self) -> &[CommandSerializer] {
+            &self._commands_buf
+        }
+
+        /// the files and the directories of the `__CWD` section, available once the syntax tree is interpreted
+        pub fn fs_actions(&self) -> &[ExacutableFsAction] {
+            &self._fs_actions_buf
         }
 
         pub fn create_tasks_executor(
             &mut self,
-            batch_size: &usize,
+            options: &Options,
             op_logger: OpArcMutex<LogWriter>,
         ) -> Option<TasksExecutor> {
-            let fs_action_op = self._fs_actions_buf.clone();
+            let fs_action_op = self
+                ._fs_actions_buf
+                .clone()
+                .into_iter()
+                .map(|mut action| {
+                    action.4 = *options.get_conflict_flag();
+                    action
+                })
+                .collect::<Vec<ExacutableFsAction>>();
             let cmd_action = self
                 ._commands_buf
                 .clone()
                 .iter_mut()
-                .map(|v| v._serialize_to_cmd())
+                .map(|v| {
+                    let mut cmd = v._serialize_to_cmd();
+                    cmd.clear_env = *options.get_clean_env_flag();
+                    cmd.raw_output = *options.get_raw_output_flag();
+                    cmd.shell = options.get_shell_flag();
+                    // an interactive command waits for the user, only its own TIMEOUT applies
+                    if !cmd.interactive {
+                        cmd.timeout = cmd.timeout.or(*options.get_timeout_flag());
+                    }
+                    cmd
+                })
                 .collect::<Vec<ExecutableTerminalCommand>>();
 
             let mut tasks: Vec<Task> = vec![];
@@ -126,79 +242,311 @@ pub mod interpreter {
                 tasks.push(task)
             }
 
+            // the folders and the tasks creating them, used to make scripts wait for their working directory
+            let folders: Vec<(Vec<String>, Task)> = self
+                ._fs_actions_buf
+                .iter()
+                .zip(tasks.iter())
+                .filter(|(action, _)| !action.1)
+                .map(|(action, task)| (path_components(&action.0), task.clone()))
+                .collect();
+
             for action in cmd_action {
-                let defered = action.1;
+                let defered = action.defered;
                 let thread_logger = op_logger.clone();
+                let cwd = action.cwd.as_deref().map(path_components);
 
-                let task = Task::new(action, defered, i, thread_logger);
+                let mut task = Task::new(action, defered, i, thread_logger);
+                if let Some(cwd) = cwd {
+                    for (_, folder) in folders
+                        .iter()
+                        .filter(|(path, _)| !path.is_empty() && cwd.starts_with(path))
+                    {
+                        task.depends_on(folder)
+                    }
+                }
                 i += 1;
                 tasks.push(task)
             }
 
             let schedular = TaskSchedular::new(tasks);
-            let executor = TasksExecutor::new(schedular, *batch_size);
+            let executor = TasksExecutor::new(schedular, *options.get_batch_size_flag());
 
             Some(executor)
         }
 
         fn variable_parsing(&mut self, variable_branch: Branch) -> Result<(), InterpreterError> {
-            for node in variable_branch.nodes.clone() {
-                if node.get_words().len() != 3 {
-                    return Err(InterpreterError::new("interpreting variables", "not enough, or more tokens has been used, 
-                                                                                            \rhint: consider checking your syntax"));
+            // the words without the empty ones, so the `=` can be aligned with spaces
+            let words = |node: &Node| {
+                node.get_words()
+                    .into_iter()
+                    .filter(|word| !word.is_empty())
+                    .collect::<Vec<String>>()
+            };
+
+            for node in variable_branch.nodes.iter() {
+                if words(node).len() != 3 {
+                    return Err(InterpreterError::new("interpreting variables", format!("not enough, or more tokens has been used at line {}, hint: consider checking your syntax", node.line).as_str()));
                 }
 
-                if !node.get_words().contains(&"=".to_string()) {
-                    return Err(InterpreterError::new("interpreting variables", "didn't find a '=' token, Note that you can only declare vaiables in this section"));
+                if words(node)[1] != "=" {
+                    return Err(InterpreterError::new("interpreting variables", format!("didn't find a '=' token at line {}, Note that you can only declare vaiables in this section", node.line).as_str()));
                 }
             }
 
-            for node in variable_branch.nodes {
-                let variable: Vec<String> =
-                    node.filter(|v| v.0 != Token::Assign).map(|v| v.1).collect();
+            for node in variable_branch.nodes.iter() {
+                let variable = words(node);
 
                 self._variable_buf
-                    .insert(variable[0].clone(), variable[1].clone())
+                    .insert(variable[0].clone(), variable[2].clone())
                     .unwrap_or("".to_string());
             }
             Ok(())
         }
 
+        fn env_parsing(&mut self, env_branch: Branch) -> Result<(), InterpreterError> {
+            let err = "interpreting the environment variables";
+
+            for node in env_branch.nodes {
+                let text = node.get_words().join(" ");
+                let (key, value) = match text.split_once('=') {
+                    Some((key, value)) => (key.trim(), value.trim()),
+                    None => {
+                        return Err(InterpreterError::new(
+                            err,
+                            format!("didn't find a '=' at line {}, environment variables are declared like 'KEY = value'", node.line).as_str(),
+                        ))
+                    }
+                };
+
+                if !is_env_key(key) {
+                    return Err(InterpreterError::new(
+                        err,
+                        format!(
+                            "'{key}' at line {} is not a valid environment variable name",
+                            node.line
+                        )
+                        .as_str(),
+                    ));
+                }
+
+                let value = self.interpolate(value, node.line)?;
+                self._env_buf.retain(|(k, _)| k != key);
+                self._env_buf.push((key.to_string(), value));
+            }
+            Ok(())
+        }
+
         fn scripts_parsing(
             &mut self,
             branch: Branch,
             command_serializers: &mut Vec<CommandSerializer>,
-        ) {
+        ) -> Result<(), InterpreterError> {
+            let err = "interpreting the scripts section";
+
             for node in branch.nodes {
+                let words = node.get_words();
                 let mut defered = false;
-                let command_name = {
-                    if let Token::DeferAction = Token::from(node.get_words()[0].clone()) {
-                        defered = true;
-                        node.get_words()[1].clone()
-                    } else {
-                        node.get_words()[0].clone()
-                    }
-                };
-                let mut command_args: Vec<String> = vec![];
+                let mut dir: Option<String> = None;
+                let mut env = self._env_buf.clone();
+                let mut timeout: Option<Duration> = None;
+                let mut retries: u32 = 0;
+                let mut backoff: Option<Duration> = None;
+                let mut interactive = false;
+                let mut heavy = false;
+                let mut idx: usize = 0;
 
-                for (tk, val) in node {
-                    match tk {
-                        Token::Arg(mut s) => {
-                            s.remove(0);
-                            command_args.push(self._variable_buf[&s].clone())
+                // the modifiers that come before the command itself (i.e `DEFER IN bin/ go mod init`)
+                while idx < words.len() {
+                    match Token::from(words[idx].clone()) {
+                        Token::DeferAction => defered = true,
+                        Token::WorkingDir => {
+                            idx += 1;
+                            match words.get(idx) {
+                                Some(path) => dir = Some(self.resolve_word(path, node.line)?),
+                                None => {
+                                    return Err(InterpreterError::new(
+                                        err,
+                                        format!(
+                                            "expected a directory after '{}' at line {}",
+                                            words[idx - 1],
+                                            node.line
+                                        )
+                                        .as_str(),
+                                    ))
+                                }
+                            }
                         }
-                        Token::Skip => {
-                            if val != command_name {
-                                command_args.push(val)
+                        Token::SetEnv => {
+                            // every `KEY=value` word after `ENV` is an override for this line only
+                            let overrides = words[idx + 1..]
+                                .iter()
+                                .map_while(|word| word.split_once('='))
+                                .take_while(|(key, _)| is_env_key(key))
+                                .map(|(key, value)| (key.to_string(), value.to_string()))
+                                .collect::<Vec<(String, String)>>();
+
+                            if overrides.is_empty() {
+                                return Err(InterpreterError::new(
+                                    err,
+                                    format!(
+                                        "expected 'KEY=value' after 'ENV' at line {}",
+                                        node.line
+                                    )
+                                    .as_str(),
+                                ));
+                            }
+
+                            idx += overrides.len();
+                            for (key, value) in overrides {
+                                let value = self.interpolate(&value, node.line)?;
+                                env.retain(|(k, _)| *k != key);
+                                env.push((key, value));
+                            }
+                        }
+                        Token::Timeout | Token::Backoff => {
+                            idx += 1;
+                            let duration = words.get(idx).and_then(|v| parse_duration(v));
+                            if duration.is_none() {
+                                return Err(InterpreterError::new(
+                                    err,
+                                    format!("expected a duration (i.e '60s', '2m' or '500ms') after '{}' at line {}", words[idx - 1], node.line).as_str(),
+                                ));
+                            }
+
+                            match Token::from(words[idx - 1].clone()) {
+                                Token::Timeout => timeout = duration,
+                                _ => backoff = duration,
                             }
                         }
-                        _ => continue,
+                        Token::Interactive => interactive = true,
+                        Token::Heavy => heavy = true,
+                        Token::Retry => {
+                            idx += 1;
+                            retries = match words.get(idx).and_then(|v| v.parse::<u32>().ok()) {
+                                Some(n) => n,
+                                None => {
+                                    return Err(InterpreterError::new(
+                                        err,
+                                        format!(
+                                        "expected the number of retries after 'RETRY' at line {}",
+                                        node.line
+                                    )
+                                        .as_str(),
+                                    ))
+                                }
+                            };
+                        }
+                        _ => break,
                     }
+                    idx += 1;
                 }
 
-                let new_command = CommandSerializer::new(command_name, command_args, defered);
+                let command_name = match words.get(idx) {
+                    Some(name) => self.resolve_word(name, node.line)?,
+                    None => {
+                        return Err(InterpreterError::new(
+                            err,
+                            format!("found no command to run at line {}", node.line).as_str(),
+                        ))
+                    }
+                };
+
+                let command_args = words[idx + 1..]
+                    .iter()
+                    .map(|word| self.resolve_word(word, node.line))
+                    .collect::<Result<Vec<String>, InterpreterError>>()?;
+
+                let cwd = match (self.node_scope(&node)?, dir) {
+                    (Some(scope), Some(dir)) => Some(scope.join(dir)),
+                    (Some(scope), None) => Some(scope),
+                    (None, dir) => dir.map(PathBuf::from),
+                };
+
+                let mut new_command = CommandSerializer::new(command_name, command_args, defered);
+                new_command.cwd = cwd.map(|dir| dir.display().to_string());
+                new_command.env = env;
+                new_command.timeout = timeout;
+                new_command.retries = retries;
+                new_command.backoff = backoff.unwrap_or(DEFAULT_BACKOFF);
+                new_command.interactive = interactive;
+                new_command.heavy = heavy;
+                new_command.line = Some(node.line);
+                new_command.line_end = Some(node.end_line);
                 command_serializers.push(new_command)
             }
+            Ok(())
+        }
+
+        /// replaces the variables in a word starting with `#` by their value (i.e `#libs_path/mylib.h`)
+        fn resolve_word(&self, word: &str, line: usize) -> Result<String, InterpreterError> {
+            match word.starts_with('#') {
+                true => self.interpolate(word, line),
+                false => Ok(word.to_string()),
+            }
+        }
+
+        /// replaces every `#var_name` in the text by the value of the variable, `##` is a literal `#`
+        fn interpolate(&self, text: &str, line: usize) -> Result<String, InterpreterError> {
+            let mut out = String::with_capacity(text.len());
+            let mut rest = text;
+
+            while let Some(pos) = rest.find('#') {
+                out.push_str(&rest[..pos]);
+                let after = &rest[pos + 1..];
+                if let Some(escaped) = after.strip_prefix('#') {
+                    out.push('#');
+                    rest = escaped;
+                    continue;
+                }
+                let name_len = after
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                let name = &after[..name_len];
+
+                if name.is_empty() {
+                    out.push('#');
+                } else {
+                    match self._variable_buf.get(name) {
+                        Some(value) => out.push_str(value),
+                        None => {
+                            return Err(InterpreterError::new(
+                                "resolving variables",
+                                format!(
+                                    "the variable '{name}' used at line {line} is not declared, write '##{name}' for a literal '#{name}'"
+                                )
+                                .as_str(),
+                            ))
+                        }
+                    }
+                }
+                rest = &after[name_len..];
+            }
+            out.push_str(rest);
+
+            Ok(out)
+        }
+
+        /// joins the directories of the `IN` blocks the node is declared in, returns `None` if the node isn't in any block
+        fn node_scope(&self, node: &Node) -> Result<Option<PathBuf>, InterpreterError> {
+            if node.scope.is_empty() {
+                return Ok(None);
+            }
+
+            let mut dir = PathBuf::new();
+            for segment in node.scope.iter() {
+                dir.push(self.resolve_word(segment, node.line)?);
+            }
+
+            Ok(Some(dir))
+        }
+
+        /// prefixes the path with the directories of the `IN` blocks the node is declared in
+        fn scoped_path(&self, node: &Node, path: String) -> Result<String, InterpreterError> {
+            match self.node_scope(node)? {
+                Some(dir) => Ok(dir.join(Path::new(&path)).display().to_string()),
+                None => Ok(path),
+            }
         }
 
         fn demos_parser(
@@ -208,31 +556,55 @@ pub mod interpreter {
         ) -> Result<(), InterpreterError> {
             let err = "interpreting the DEMO sections";
 
-            for (line, node) in branch.nodes.iter().enumerate() {
+            for node in branch.nodes.iter() {
+                let line = node.line;
                 if node.get_words().len() > 4 {
                     return Err(InterpreterError::new(
                         err,
-                        "too many tokens you can only have 3 to 4 tokens in one line",
+                        format!("too many tokens at line {line}, you can only have 3 to 4 tokens in one line").as_str(),
                     ));
                 }
 
+                let words = node.get_words();
+                let token_at = |i: usize| words.get(i).map(|word| Token::from(word.clone()));
+
                 let mut defered = false;
-                let second_token = match Token::from(node.get_words()[0].clone()) {
-                    Token::DeferAction =>{
+                let second_token = match token_at(0) {
+                    Some(Token::DeferAction) =>{
                         defered = true;
-                        Token::from(node.get_words()[2].clone())
+                        token_at(2)
                     },
-                    Token::Skip =>
-                        Token::from(node.get_words()[1].clone()),
-                    _ => return Err(InterpreterError::new(err, format!("found invalid token at {} in the demo section, all the valid tokens are COPY_INTO or DEFER", line + 1).as_str()))
+                    Some(Token::Skip) => token_at(1),
+                    _ => return Err(InterpreterError::new(err, format!("found invalid token at line {} in the demo section, all the valid tokens are COPY_INTO or DEFER", line).as_str()))
+                };
+                // the source comes before COPY_INTO and the destination after it
+                let missing = match second_token {
+                    Some(Token::CopyAction) if words.len() < if defered { 4 } else { 3 } => "the destination",
+                    Some(Token::CopyAction) => "",
+                    None => "COPY_INTO",
+                    Some(_) => return Err(InterpreterError::new(err,format!("unable to interpret line {} in the demo section, found an unexpected token .", line).as_str())),
                 };
-                if second_token != Token::CopyAction {
-                    return Err(InterpreterError::new(err,format!("unable to interpret line {} in the demo section, found an unexpected token .", line+1).as_str()));
+                if !missing.is_empty() {
+                    return Err(InterpreterError::new(err, format!("{missing} is missing at line {line} in the demo section, a copy is written like 'source COPY_INTO destination'").as_str()));
+                }
+
+                let mut args: Vec<String> = node
+                    .get_words()
+                    .iter()
+                    .filter(|v| {
+                        (Token::from(v.to_string()) != Token::CopyAction)
+                            && (Token::from(v.to_string()) != Token::DeferAction)
+                    })
+                    .map(|v| self.resolve_word(v, line))
+                    .collect::<Result<Vec<String>, InterpreterError>>()?;
+
+                // only the destination is inside the `IN` block, the source is relative to where tmplt is executed
+                if let Some(dest) = args.pop() {
+                    args.push(self.scoped_path(node, dest)?);
                 }
 
                 if cfg!(target_os = "windows") {
-                    let filtered = node
-                        .get_words()
+                    let filtered = args
                         .iter()
                         .filter(|v| v.contains('/'))
                         .cloned()
@@ -241,22 +613,20 @@ pub mod interpreter {
                     if !filtered.is_empty() {
                         return Err(InterpreterError::new(
                             err,
-                            format!("make sure to use backslashes '\\' error occurs at {}", line)
-                                .as_str(),
+                            format!(
+                                "make sure to use backslashes '\\' error occurs at line {}",
+                                line
+                            )
+                            .as_str(),
                         ));
                     }
                 }
-                let args: Vec<String> = node
-                    .get_words()
-                    .iter()
-                    .filter(|v| {
-                        (Token::from(v.to_string()) != Token::CopyAction)
-                            && (Token::from(v.to_string()) != Token::DeferAction)
-                    })
-                    .cloned()
-                    .collect();
 
-                let command = CommandSerializer::new("copy".to_string(), args, defered);
+                let mut command = CommandSerializer::new("copy".to_string(), args, defered);
+                command.env = self._env_buf.clone();
+                command.line = Some(line);
+                command.line_end = Some(node.end_line);
+                command.demo = true;
 
                 command_serializers.push(command)
             }
@@ -270,14 +640,15 @@ pub mod interpreter {
         ) -> Result<(), InterpreterError> {
             let err = "interpreting the directories structure";
 
-            for (line, node) in branch.nodes.iter().enumerate() {
+            for node in branch.nodes.iter() {
+                let line = node.line;
                 let words = node.get_words();
                 if words.len() > 3 {
                     return Err(InterpreterError::new(err, format!("unable to interpret at line {} in the directories section, found too mush tokens.", line).as_str()));
                 }
 
                 if words.len() == 3 && words[0].to_uppercase() != *"DEFER" {
-                    return Err(InterpreterError::new(err, format!("Unvalid token at {}:0 in the directories section expected DEFER found {}",line + 1 ,words[0].clone()).as_str()));
+                    return Err(InterpreterError::new(err, format!("Unvalid token at {}:0 in the directories section expected DEFER found {}",line ,words[0].clone()).as_str()));
                 }
 
                 let mut defered = false;
@@ -288,29 +659,133 @@ pub mod interpreter {
                     match tk {
                         Token::DeclareFile => is_file = true,
                         Token::DeferAction => defered = true,
-                        Token::Arg(mut v) => {
-                            v.remove(0);
-                            path = self._variable_buf[&v].clone()
-                        }
+                        Token::Arg(v) => path = self.resolve_word(&v, line)?,
                         Token::Skip => path = val,
                         _ => (),
                     }
                 }
 
-                let fs_action = ExacutableFsAction(path, is_file, defered);
+                let path = self.scoped_path(node, path)?;
+                let fs_action = ExacutableFsAction(path, is_file, defered, line, None);
                 fs_actions.push(fs_action)
             }
             Ok(())
         }
     }
 
+    /// environment variable names are made of letters, digits and underscores and don't start with a digit
+    fn is_env_key(key: &str) -> bool {
+        !key.is_empty()
+            && !key.starts_with(|c: char| c.is_ascii_digit())
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    /// splits a path into its components so `./bin\\` and `bin/` are considered the same directory,
+    /// the `..` are resolved (`a/../b` is `b`) and an absolute path starts with `/`
+    pub fn path_components(path: &str) -> Vec<String> {
+        let path = path.replace('\\', "/");
+        // an absolute path keeps its root, `/src` isn't the `src` of the current directory
+        let mut components = match path.starts_with('/') {
+            true => vec!["/".to_string()],
+            false => vec![],
+        };
+
+        for component in path.split('/').filter(|v| !v.is_empty() && *v != ".") {
+            match components.last().map(|last| last.as_str()) {
+                // there is nothing above the root
+                Some("/") if component == ".." => (),
+                Some(last) if component == ".." && last != ".." => {
+                    components.pop();
+                }
+                _ => components.push(component.to_string()),
+            }
+        }
+        components
+    }
+
+    /// what a command gives back once it is executed, whether it succeeded or not
+    pub trait CommandOutput: Display {
+        /// the exit code of the process, `None` if no process was started or it got killed
+        fn exit_code(&self) -> Option<i32> {
+            None
+        }
+
+        fn stdout(&self) -> &str {
+            ""
+        }
+
+        fn stderr(&self) -> &str {
+            ""
+        }
+
+        /// the timeout the command was stopped after, if it ran for too long
+        fn timed_out(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    impl CommandOutput for String {}
+
     pub trait ExecutableCommand<O>
     where
-        O: Display,
+        O: CommandOutput,
     {
-        fn _execute(&self) -> Option<O>;
+        /// executes the command, the output is given back on failure as well so it can be reported
+        fn _execute(&self, context: &TaskContext) -> Result<O, O>;
+
+        /// the line of the template the command comes from
+        fn line(&self) -> Option<usize> {
+            None
+        }
+
+        /// the last line of the command in the template, the same as `line` unless it is continued with ` \`
+        fn line_end(&self) -> Option<usize> {
+            self.line()
+        }
+
+        /// what kind of task executes the command, `command` or `fs_action`
+        fn kind(&self) -> &'static str {
+            "command"
+        }
+
+        /// a short description of what gets executed, used when reporting the task
+        fn describe(&self) -> String;
+
+        /// an interactive command uses the terminal, so it has to run while no other task does
+        fn is_interactive(&self) -> bool {
+            false
+        }
+
+        /// a heavy command only runs alongside a few other heavy commands (see `--heavy-jobs`)
+        fn resource_class(&self) -> ResourceClass {
+            ResourceClass::Light
+        }
     }
 
+    /// the time waited before the first retry of a failed script, it doubles after every retry
+    const DEFAULT_BACKOFF: Duration = Duration::from_secs(1);
+
+    /// how often a running command is checked for exiting, timing out or being cancelled
+    const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+    /// how many bytes of the stdout (and the stderr) of a command are kept in memory
+    const OUTPUT_MEMORY_CAP: usize = 256 * 1024;
+
+    /// a line longer than this is split, so a command printing binary data doesn't fill the memory
+    const MAX_LINE_LENGTH: u64 = 64 * 1024;
+
+    /// commands (the program and its first arg) that are heavy even without the `HEAVY` modifier
+    const HEAVY_COMMANDS: [(&str, &str); 8] = [
+        ("npm", "install"),
+        ("npm", "i"),
+        ("npm", "ci"),
+        ("yarn", "install"),
+        ("pnpm", "install"),
+        ("cargo", "build"),
+        ("cargo", "install"),
+        ("go", "build"),
+    ];
+
     /// contains a command that will be serielized to be executable
     #[derive(Clone, Debug)]
     pub struct CommandSerializer {
@@ -318,23 +793,58 @@ pub mod interpreter {
 
         pub command_name: String,
         pub defered: bool,
+        /// the directory the command runs in, `None` means the directory tmplt is executed in
+        pub cwd: Option<String>,
+        /// the environment variables set for the command, the `__ENV` section followed by the `ENV` overrides of the line
+        pub env: Vec<(String, String)>,
+        /// the command is stopped if it runs longer than this
+        pub timeout: Option<Duration>,
+        /// how many times the command is run again if it fails
+        pub retries: u32,
+        /// the time waited before the first retry, it doubles after every retry
+        pub backoff: Duration,
+        /// the command runs in the terminal (it can read from stdin) and no other task runs meanwhile
+        pub interactive: bool,
+        /// the command was marked with `HEAVY`
+        pub heavy: bool,
+        /// the line of the template the command comes from
+        pub line: Option<usize>,
+        /// the last line of the command, when it is continued over several lines
+        pub line_end: Option<usize>,
+        /// the command copies the files of a `__DEMO` line, its args are the sources followed by the destination
+        pub demo: bool,
     }
 
-    /// a named tuple for specifiy if the command should be executed without order or should be defered
-    ///
-    /// simple format (args, defered)
+    /// a terminal command that is ready to be executed
     #[derive(Debug, Clone)]
-    pub struct ExecutableTerminalCommand(
-        /// a vector of strings (`Vec<String>`) that represent the args to the command that will be run
-        Vec<String>,
+    pub struct ExecutableTerminalCommand {
+        /// a vector of strings (`Vec<String>`) that represent the command name followed by its args
+        args: Vec<String>,
+        /// the directory the command runs in, `None` means the directory tmplt is executed in
+        cwd: Option<String>,
+        env: Vec<(String, String)>,
+        /// if the inherited environment should be cleared before setting `env`, `PATH` is always kept
+        /// so the command can still be found
+        pub clear_env: bool,
+        pub timeout: Option<Duration>,
+        retries: u32,
+        backoff: Duration,
+        interactive: bool,
+        heavy: bool,
+        line: Option<usize>,
+        line_end: Option<usize>,
+        /// print the output as the command wrote it instead of replacing invalid utf8
+        pub raw_output: bool,
+        /// the shell the command runs in followed by its arguments (i.e `bash -c`), `sh -c` (`cmd /C` on windows) by default
+        pub shell: Option<Vec<String>>,
         /// this `boolean` represent if the current action should be executed in the order it is placed in
         /// or to run after all task has finished
-        pub bool,
-    );
+        pub defered: bool,
+    }
 
     /// a named tuple to simplify the creatio of a file/directory
     ///
-    /// simple format (path, is_file, defered)
+    /// simple format (path, is_file, defered, line, conflict policy)
     #[derive(Debug, Clone)]
     pub struct ExacutableFsAction(
         /// this `string` represent the path of the file or the directory to create
@@ -344,102 +854,520 @@ pub mod interpreter {
         /// this `boolean` represent if the current action should be executed in the order it is placed in
         /// or to run after all task has finished
         pub bool,
+        /// the line of the template the action comes from
+        usize,
+        /// what to do if the path already exists, set with `--conflict`
+        pub Option<ConflictPolicy>,
     );
 
+    /// the output of a command, it is already streamed to the terminal while the command runs
+    ///
+    /// only the end of each stream is kept in memory (see `OUTPUT_MEMORY_CAP`), the rest is only in the log file
+    /// when the logs are saved
     #[derive(Clone)]
     pub struct CmdOut {
+        command: String,
         stdout: String,
         stderr: String,
+        /// the bytes of stdout and stderr as the command printed them, only kept with `--raw-output`
+        raw: Option<(Vec<u8>, Vec<u8>)>,
+        lines: usize,
+        /// how many bytes didn't fit in memory
+        dropped: usize,
+        /// if the bytes that didn't fit in memory were written to the log file
+        spilled: bool,
+        exit_code: Option<i32>,
+        /// the timeout the command was stopped after
+        timed_out: Option<Duration>,
     }
 
     impl CmdOut {
-        pub fn new(stdout: Vec<u8>, stderr: Vec<u8>) -> Self {
-            let msg = "unable to parse String from utf8, Details: the stdout/stderr outputed a unvalid utf8";
+        pub fn new(
+            command: String,
+            stdout: PipeOutput,
+            stderr: PipeOutput,
+            keep_raw: bool,
+        ) -> Self {
+            // a command can print anything, bytes that aren't valid utf8 are replaced instead of failing the task
+            let out = String::from_utf8_lossy(&stdout.bytes).into_owned();
+            let err = String::from_utf8_lossy(&stderr.bytes).into_owned();
 
-            let out = String::from_utf8(stdout).expect(msg);
-            let err = String::from_utf8(stderr).expect(msg);
             CmdOut {
+                command,
                 stdout: out,
                 stderr: err,
+                lines: stdout.lines + stderr.lines,
+                dropped: stdout.dropped + stderr.dropped,
+                spilled: stdout.spilled && stderr.spilled,
+                exit_code: None,
+                timed_out: None,
+                raw: match keep_raw {
+                    true => Some((stdout.bytes, stderr.bytes)),
+                    false => None,
+                },
             }
         }
+
+        /// the output of a command that couldn't be started
+        fn empty(command: String) -> Self {
+            CmdOut::new(command, PipeOutput::default(), PipeOutput::default(), false)
+        }
+
+        /// the output bytes (stdout, stderr) exactly as the command printed them, only kept with `--raw-output`
+        pub fn raw(&self) -> Option<&(Vec<u8>, Vec<u8>)> {
+            self.raw.as_ref()
+        }
+    }
+
+    impl CommandOutput for CmdOut {
+        fn exit_code(&self) -> Option<i32> {
+            self.exit_code
+        }
+
+        fn stdout(&self) -> &str {
+            &self.stdout
+        }
+
+        fn stderr(&self) -> &str {
+            &self.stderr
+        }
+
+        fn timed_out(&self) -> Option<Duration> {
+            self.timed_out
+        }
     }
 
     impl fmt::Display for CmdOut {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            if self.stdout.is_empty() {
-                let status: String = LogStatus::Info.into();
-                let to_print = self
-                    .stderr
-                    .replace('\n', format!("\n{}", logformat!("", status)).as_str());
-                write!(f, "{}", to_print)
-            } else {
-                write!(f, "{}", self.stdout)
+            write!(
+                f,
+                "finished '{}' ({} lines of output)",
+                self.command, self.lines
+            )?;
+
+            match (self.dropped, self.spilled) {
+                (0, _) => (),
+                (dropped, true) => write!(
+                    f,
+                    ", the first {dropped} bytes of output were only kept in the log file"
+                )?,
+                (dropped, false) => write!(
+                    f,
+                    ", the first {dropped} bytes of output were discarded (save the logs to keep them)"
+                )?,
             }
+            Ok(())
         }
     }
 
-    impl ExecutableCommand<CmdOut> for ExecutableTerminalCommand {
-        #[cfg(target_os = "windows")]
-        fn _execute(&self) -> Option<CmdOut> {
-            let mut cmd = Command::new("cmd");
+    /// what was read from the stdout or the stderr of a command
+    #[derive(Clone, Default)]
+    pub struct PipeOutput {
+        /// the last `OUTPUT_MEMORY_CAP` bytes of the stream
+        bytes: Vec<u8>,
+        lines: usize,
+        /// how many bytes from the start of the stream were dropped from memory
+        dropped: usize,
+        /// if the dropped bytes are in the log file
+        spilled: bool,
+    }
 
-            let mut serilized = vec!["/C".to_owned()];
-            let mut args = self.0.clone();
-            serilized.append(&mut args);
+    impl ExecutableTerminalCommand {
+        #[cfg(target_os = "windows")]
+        fn command(&self) -> Command {
+            use std::os::windows::process::CommandExt;
+            /// keeps ctrl-c from reaching the command, tmplt stops it itself
+            const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
 
-            let command = cmd.args(serilized);
-            match command.output() {
-                Ok(raw_out) => {
-                    let out = CmdOut::new(raw_out.stdout, raw_out.stderr);
-                    Some(out)
+            let mut cmd = match &self.shell {
+                Some(shell) => {
+                    let mut cmd = Command::new(&shell[0]);
+                    cmd.args(&shell[1..]).arg(self.args.join(" "));
+                    cmd
                 }
-                Err(e) => {
-                    eprintln!("{e}");
-                    None
+                None => {
+                    let mut cmd = Command::new("cmd");
+                    cmd.arg("/C").args(&self.args);
+                    cmd
                 }
+            };
+            if !self.interactive {
+                cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
             }
+            cmd
         }
 
         #[cfg(not(target_os = "windows"))]
-        fn _execute(&self) -> Option<CmdOut> {
-            let mut sh = Command::new("sh");
+        fn command(&self) -> Command {
+            use std::os::unix::process::CommandExt;
+
+            let (shell, shell_args) = match &self.shell {
+                Some(shell) => (shell[0].as_str(), &shell[1..]),
+                None => ("sh", &["-c".to_string()][..]),
+            };
+            let mut sh = Command::new(shell);
+
+            sh.args(shell_args).arg(self.args.join(" "));
+            // its own process group so the command and everything it starts can be stopped together,
+            // an interactive command stays in the terminal's foreground group so it can read from it
+            if !self.interactive {
+                sh.process_group(0);
+            }
+            sh
+        }
+
+        /// applies the working directory and the environment variables to the command
+        fn configure(&self, command: &mut Command) {
+            if let Some(dir) = &self.cwd {
+                command.current_dir(dir);
+            }
 
-            let command = sh.args(self.0.clone());
-            match command.output() {
-                Ok(out) => {
-                    let out = CmdOut::new(out.stdout, out.stderr);
-                    Some(out)
+            if self.clear_env {
+                command.env_clear();
+                // `cmd` can't start without these on windows
+                for key in ["PATH", "SystemRoot", "ComSpec"] {
+                    if let Ok(value) = std::env::var(key) {
+                        command.env(key, value);
+                    }
                 }
-                Err(e) => {
-                    eprintln!("{e}");
-                    None
+            }
+
+            command.envs(self.env.iter().map(|(k, v)| (k, v)));
+        }
+
+        /// the prefix of the output lines of the command, i.e `[task 3 go get]`
+        fn output_prefix(&self, id: usize) -> String {
+            let mut name = self
+                .args
+                .iter()
+                .take(2)
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(" ");
+            if name.chars().count() > 24 {
+                name = name.chars().take(23).collect::<String>() + "…";
+            }
+            format!("[task {id} {name}]")
+        }
+
+        /// runs the command once, on failure returns the reason and the output (empty if the command couldn't start)
+        fn run_once(&self, context: &TaskContext) -> Result<CmdOut, (String, Box<CmdOut>)> {
+            let mut command = self.command();
+            self.configure(&mut command);
+            match self.interactive {
+                // with `--output=json` the stdout of tmplt only has events, what the command prints goes to stderr
+                true if events::enabled() => command
+                    .stdin(Stdio::inherit())
+                    .stdout(io::stderr())
+                    .stderr(Stdio::inherit()),
+                true => command
+                    .stdin(Stdio::inherit())
+                    .stdout(Stdio::inherit())
+                    .stderr(Stdio::inherit()),
+                false => command
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped()),
+            };
+
+            let mut child = command
+                .spawn()
+                .map_err(|e| (e.to_string(), Box::new(CmdOut::empty(self.describe()))))?;
+            register_child(&child);
+
+            let prefix = self.output_prefix(context.id);
+            let stdout = stream_pipe(
+                child.stdout.take(),
+                Stream::Stdout,
+                &prefix,
+                self.raw_output,
+                context,
+            );
+            let stderr = stream_pipe(
+                child.stderr.take(),
+                Stream::Stderr,
+                &prefix,
+                self.raw_output,
+                context,
+            );
+
+            let start = Instant::now();
+            let mut exit_status = None;
+            let mut timed_out = None;
+            // waits for the command to exit and for its output to be fully read, a process started in the
+            // background by the command can keep the pipes open after the command itself exits
+            let status = loop {
+                if exit_status.is_none() {
+                    match child.try_wait() {
+                        Ok(status) => exit_status = status,
+                        Err(e) => break Err(e.to_string()),
+                    }
                 }
+
+                // checked first, cancelling stops the command which would look like it exited by itself
+                if is_cancelled() {
+                    terminate_child(&mut child);
+                    break Err("cancelled".to_string());
+                }
+
+                if let Some(status) = exit_status {
+                    if stdout.is_finished() && stderr.is_finished() {
+                        break Ok(status);
+                    }
+                }
+
+                if let Some(timeout) = self.timeout {
+                    if start.elapsed() >= timeout {
+                        terminate_child(&mut child);
+                        timed_out = Some(timeout);
+                        break Err(format!("timed out after {timeout:?}"));
+                    }
+                }
+
+                thread::sleep(POLL_INTERVAL);
+            };
+            unregister_child(&child);
+
+            let mut out = CmdOut::new(
+                self.describe(),
+                stdout.join().unwrap_or_default(),
+                stderr.join().unwrap_or_default(),
+                self.raw_output,
+            );
+            out.timed_out = timed_out;
+
+            match status {
+                Ok(status) if status.success() => {
+                    out.exit_code = status.code();
+                    Ok(out)
+                }
+                Ok(status) => {
+                    out.exit_code = status.code();
+                    Err((format!("exited with {status}"), Box::new(out)))
+                }
+                Err(reason) => Err((reason, Box::new(out))),
             }
         }
     }
 
-    impl ExecutableCommand<String> for ExacutableFsAction {
-        fn _execute(&self) -> Option<String> {
-            // checks if the path provided leads to a file
-            if self.1 {
-                match fs::File::create(&self.0) {
-                    Ok(_) => Some(format!("successfuly created file : {}", self.0)),
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        None
+    /// reads the pipe line by line in a seperate thread, every line is printed with the prefix and logged
+    /// as soon as it is read. returns the end of what was read once the pipe is closed
+    ///
+    /// with `raw` the lines are printed to the terminal as they are, otherwise invalid utf8 is replaced
+    fn stream_pipe<R: Read + Send + 'static>(
+        pipe: Option<R>,
+        stream: Stream,
+        prefix: &str,
+        raw: bool,
+        context: &TaskContext,
+    ) -> JoinHandle<PipeOutput> {
+        let prefix = prefix.to_string();
+        let logger = context.logger.clone();
+        let id = context.id;
+
+        thread::spawn(move || {
+            let mut output = PipeOutput {
+                spilled: logger.is_some(),
+                ..Default::default()
+            };
+            // the last lines read and their size in bytes
+            let mut kept: VecDeque<Vec<u8>> = VecDeque::new();
+            let mut kept_len: usize = 0;
+            let mut reader = match pipe {
+                Some(pipe) => BufReader::new(pipe),
+                None => return output,
+            };
+
+            let mut line: Vec<u8> = vec![];
+            while let Ok(n) = (&mut reader)
+                .take(MAX_LINE_LENGTH)
+                .read_until(b'\n', &mut line)
+            {
+                if n == 0 {
+                    break;
+                }
+                output.lines += 1;
+
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim_end_matches(['\n', '\r']);
+                // with `-q` only what the commands write to stderr is shown
+                let (status, marker) = match stream {
+                    Stream::Stdout => (LogStatus::Info, ""),
+                    Stream::Stderr => (LogStatus::Warning, "[stderr]"),
+                };
+                let painted = || console::paint(stream, &prefix, Color::Cyan);
+                match (events::enabled(), raw) {
+                    (true, _) => events::task_output(id, stream, text),
+                    _ if !log_enabled(&status) => (),
+                    (false, true) => console::write_raw_line(stream, &painted(), &line),
+                    (false, false) => {
+                        console::write_line(stream, format!("{} {text}", painted()).as_str())
                     }
                 }
-            // this else means that if the path is not a file then it is a directory
-            } else {
-                match fs::create_dir(&self.0) {
-                    Ok(()) => Some(format!("successfuly created directory : {}", self.0)),
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        None
+
+                if let Some(logger) = &logger {
+                    let mut log = logger.lock().unwrap();
+                    log.write(format!("{prefix}{marker} {text}"), status.clone())
+                }
+
+                kept_len += line.len();
+                kept.push_back(std::mem::take(&mut line));
+                // the oldest lines are dropped, the ones the log level hid are written to the log anyway
+                while kept_len > OUTPUT_MEMORY_CAP && kept.len() > 1 {
+                    let old = kept.pop_front().unwrap_or_default();
+                    kept_len -= old.len();
+                    output.dropped += old.len();
+                    match &logger {
+                        Some(logger) if !log_enabled(&status) => {
+                            let text = String::from_utf8_lossy(&old);
+                            let text = text.trim_end_matches(['\n', '\r']);
+                            let mut log = logger.lock().unwrap();
+                            log.spill(format!("{prefix}{marker} {text}"), status.clone())
+                        }
+                        _ => (),
+                    }
+                }
+            }
+
+            output.bytes = kept.into_iter().flatten().collect();
+            output
+        })
+    }
+
+    /// writes the line to the log file of the run, if the logs are saved
+    fn log_line(context: &TaskContext, line: String, status: LogStatus) {
+        if let Some(logger) = &context.logger {
+            logger.lock().unwrap().write(line, status)
+        }
+    }
+
+    impl ExecutableCommand<CmdOut> for ExecutableTerminalCommand {
+        fn _execute(&self, context: &TaskContext) -> Result<CmdOut, CmdOut> {
+            let attempts = self.retries + 1;
+            let mut backoff = self.backoff;
+
+            let mut attempt = 1;
+            loop {
+                let (reason, out) = match self.run_once(context) {
+                    Ok(out) => return Ok(out),
+                    Err(failure) => failure,
+                };
+
+                if is_cancelled() {
+                    return Err(*out);
+                }
+
+                if attempt == attempts {
+                    let line = format!("'{}' {reason}", self.describe());
+                    logprint!(LogStatus::Error, "{line}");
+                    log_line(context, line, LogStatus::Error);
+                    return Err(*out);
+                }
+
+                let line = format!(
+                    "'{}' {reason} (attempt {attempt}/{attempts}), retrying in {backoff:?}",
+                    self.describe()
+                );
+                logprint!(LogStatus::Warning, "{line}");
+                log_line(context, line, LogStatus::Warning);
+
+                if !sleep_unless_cancelled(backoff) {
+                    return Err(*out);
+                }
+                backoff = backoff.saturating_mul(2);
+                attempt += 1;
+            }
+        }
+
+        fn describe(&self) -> String {
+            self.args.join(" ")
+        }
+
+        fn is_interactive(&self) -> bool {
+            self.interactive
+        }
+
+        fn line(&self) -> Option<usize> {
+            self.line
+        }
+
+        fn line_end(&self) -> Option<usize> {
+            self.line_end
+        }
+
+        fn resource_class(&self) -> ResourceClass {
+            let known_heavy = HEAVY_COMMANDS.iter().any(|(name, sub)| {
+                self.args.first().is_some_and(|v| v == name)
+                    && self.args.get(1).is_some_and(|v| v == sub)
+            });
+
+            match self.heavy || known_heavy {
+                true => ResourceClass::Heavy,
+                false => ResourceClass::Light,
+            }
+        }
+    }
+
+    impl ExacutableFsAction {
+        pub fn path(&self) -> &str {
+            &self.0
+        }
+
+        pub fn is_file(&self) -> bool {
+            self.1
+        }
+    }
+
+    impl ExecutableCommand<String> for ExacutableFsAction {
+        fn _execute(&self, _context: &TaskContext) -> Result<String, String> {
+            if Path::new(&self.0).exists() {
+                match (self.4, self.1) {
+                    (Some(ConflictPolicy::Error), _) => {
+                        let error = format!("unable to create {} : it already exists", self.0);
+                        logprint!(LogStatus::Error, "{error}");
+                        return Err(error);
                     }
+                    (Some(ConflictPolicy::Skip), _) => {
+                        return Ok(format!("{} already exists, skipped", self.0))
+                    }
+                    (Some(ConflictPolicy::Overwrite), true) => {
+                        logprint!(LogStatus::ForcedAction, "overwriting the file {}", self.0)
+                    }
+                    (Some(ConflictPolicy::Overwrite), false) => {
+                        return Ok(format!("using the existing directory : {}", self.0))
+                    }
+                    (None, _) => (),
                 }
             }
+
+            // checks if the path provided leads to a file
+            let result = match self.1 {
+                true => fs::File::create(&self.0)
+                    .map(|_| format!("successfuly created file : {}", self.0)),
+                // this means that if the path is not a file then it is a directory
+                false => fs::create_dir(&self.0)
+                    .map(|_| format!("successfuly created directory : {}", self.0)),
+            };
+
+            result.map_err(|e| {
+                eprintln!("{}", e);
+                format!("unable to create {} : {e}", self.0)
+            })
+        }
+
+        fn line(&self) -> Option<usize> {
+            Some(self.3)
+        }
+
+        fn kind(&self) -> &'static str {
+            "fs_action"
+        }
+
+        fn describe(&self) -> String {
+            match self.1 {
+                true => format!("create file {}", self.0),
+                false => format!("create directory {}", self.0),
+            }
         }
     }
     impl CommandSerializer {
@@ -448,14 +1376,125 @@ pub mod interpreter {
                 _args: args,
                 command_name,
                 defered,
+                cwd: None,
+                env: vec![],
+                timeout: None,
+                retries: 0,
+                backoff: DEFAULT_BACKOFF,
+                interactive: false,
+                heavy: false,
+                line: None,
+                line_end: None,
+                demo: false,
             }
         }
 
+        pub fn args(&self) -> &[String] {
+            &self._args
+        }
+
         pub fn _serialize_to_cmd(&mut self) -> ExecutableTerminalCommand {
             let mut serilized_args = vec![self.command_name.clone()];
             serilized_args.append(&mut self._args);
 
-            ExecutableTerminalCommand(serilized_args, self.defered)
+            ExecutableTerminalCommand {
+                args: serilized_args,
+                cwd: self.cwd.clone(),
+                env: self.env.clone(),
+                clear_env: false,
+                timeout: self.timeout,
+                retries: self.retries,
+                backoff: self.backoff,
+                interactive: self.interactive,
+                heavy: self.heavy,
+                line: self.line,
+                line_end: self.line_end,
+                raw_output: false,
+                shell: None,
+                defered: self.defered,
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::interpreter::*;
+    use crate::core::{Parser, Tree};
+
+    fn interpret(data: &str) -> Result<Interpreter, InterpreterError> {
+        let lines = Parser::new("tmplt".to_string())
+            .parse_data(data.to_string())
+            .unwrap();
+        let mut interpreter = Interpreter::construct(Tree::construct(lines).unwrap());
+        interpreter.interpret()?;
+        Ok(interpreter)
+    }
+
+    #[test]
+    fn variables_are_replaced() {
+        let interpreter = interpret(
+            "__VAR:\n    out = build\n__ENV:\n    DIR = #out/bin\n__SCRIPTS:\n    ls #out\n",
+        )
+        .unwrap();
+        let command = &interpreter.commands()[0];
+        assert_eq!(command.env, [("DIR".to_string(), "build/bin".to_string())]);
+        assert_eq!(command.args(), ["build"]);
+    }
+
+    #[test]
+    fn a_double_hash_is_a_literal_hash() {
+        let interpreter =
+            interpret("__ENV:\n    COLOR = ##fff\n__SCRIPTS:\n    ENV BG=##000 echo ##1\n")
+                .unwrap();
+        let command = &interpreter.commands()[0];
+        assert_eq!(
+            command.env,
+            [
+                ("COLOR".to_string(), "#fff".to_string()),
+                ("BG".to_string(), "#000".to_string())
+            ]
+        );
+        assert_eq!(command.args(), ["#1"]);
+    }
+
+    #[test]
+    fn an_undeclared_variable_is_an_error() {
+        let e = interpret("__ENV:\n    COLOR = #fff\n").unwrap_err();
+        assert!(e.describe().contains("'##fff'"), "{}", e.describe());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_script_running_too_long_times_out() {
+        use std::time::{Duration, Instant};
+
+        use crate::tasks::{Task, TaskContext, TaskStatus};
+
+        let interpreter = interpret("__SCRIPTS:\n    TIMEOUT 200ms sleep 5\n").unwrap();
+        let mut command = interpreter.commands()[0].clone();
+        let command = command._serialize_to_cmd();
+
+        let start = Instant::now();
+        let context = TaskContext {
+            id: 0,
+            logger: None,
+        };
+        let out = match command._execute(&context) {
+            Ok(_) => panic!("sleep 5 finished before its timeout"),
+            Err(out) => out,
+        };
+        assert_eq!(out.timed_out(), Some(Duration::from_millis(200)));
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        let outcome = Task::new(command, false, 0, None).execute_task();
+        assert_eq!(
+            outcome.status,
+            TaskStatus::TimedOut(Duration::from_millis(200))
+        );
+        assert_eq!(
+            outcome.to_string(),
+            "task 0 (sleep 5) timed out after 200ms (line 2)"
+        );
+    }
+}
@@ -0,0 +1,515 @@
+/// the `__META` section, tells what the template is for and what it needs to run:
+/// ```text
+/// __META:
+///     name = go-api
+///     description = a REST api in go with a postgres database
+///     version = 1.2.0
+///     min_tmplt_version = 0.1
+///     requires = go, node>=18
+/// ```
+/// nothing in it is executed, the requirements are checked before any task runs
+pub mod metadata {
+    use std::cmp::Ordering;
+    use std::env;
+    use std::ffi::OsStr;
+    use std::fmt;
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::core::Tokens::SectionIdentity;
+    use crate::core::Tree;
+    use crate::tasks::terminate_child;
+
+    /// how long `<program> --version` has to print the version of a required program
+    const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// how often the `--version` probe is checked for its exit
+    const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+    /// the keys of the `__META` section
+    pub const META_KEYS: &[&str] = &[
+        "name",
+        "description",
+        "version",
+        "min_tmplt_version",
+        "requires",
+    ];
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Metadata {
+        pub name: Option<String>,
+        pub description: Option<String>,
+        /// the version of the template itself, only displayed
+        pub version: Option<String>,
+        /// the oldest tmplt that can run the template
+        pub min_tmplt_version: Option<Version>,
+        /// the programs the scripts need, in the order they are declared
+        pub requires: Vec<Requirement>,
+    }
+
+    /// a program that has to be on `PATH`, i.e `go` or `node>=18`
+    #[derive(Clone, Debug)]
+    pub struct Requirement {
+        pub program: String,
+        pub min_version: Option<Version>,
+        pub line: usize,
+    }
+
+    /// a version like `1.21` or `18.2.0`, compared number by number so `1.9` is older than `1.10`
+    /// and a missing number counts as 0 (`18` is `18.0.0`)
+    #[derive(Clone, Debug)]
+    pub struct Version(Vec<u64>);
+
+    #[derive(Clone, Debug)]
+    pub struct MetadataError {
+        line: usize,
+        reason: String,
+    }
+
+    impl MetadataError {
+        pub fn new(line: usize, reason: &str) -> Self {
+            MetadataError {
+                line,
+                reason: reason.to_string(),
+            }
+        }
+    }
+
+    impl fmt::Display for MetadataError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "in the metadata section at line {}: {}",
+                self.line, self.reason
+            )
+        }
+    }
+
+    impl Metadata {
+        /// the metadata of the `__META` section, an empty one if the template doesn't have it
+        pub fn from_tree(tree: &Tree) -> Result<Self, MetadataError> {
+            let mut metadata = Metadata::default();
+            let branch = match tree
+                .branches
+                .iter()
+                .find(|branch| branch.section_kind == SectionIdentity::Metadata)
+            {
+                Some(branch) => branch,
+                None => return Ok(metadata),
+            };
+
+            for node in branch.nodes.iter() {
+                let text = node.get_words().join(" ");
+                let (key, value) = match text.split_once('=') {
+                    Some((key, value)) => (key.trim().replace('-', "_"), unquote(value.trim())),
+                    None => {
+                        return Err(MetadataError::new(
+                            node.line,
+                            "didn't find a '=', the metadata is declared like 'key = value'",
+                        ))
+                    }
+                };
+                if value.is_empty() {
+                    return Err(MetadataError::new(
+                        node.line,
+                        &format!("the value of '{key}' is missing"),
+                    ));
+                }
+
+                match key.as_str() {
+                    "name" => metadata.name = Some(value),
+                    "description" => metadata.description = Some(value),
+                    "version" => metadata.version = Some(value),
+                    "min_tmplt_version" => {
+                        let version = Version::parse(&value).ok_or_else(|| {
+                            MetadataError::new(
+                                node.line,
+                                &format!("'{value}' isn't a version, i.e '0.2' or '1.0.3'"),
+                            )
+                        })?;
+                        metadata.min_tmplt_version = Some(version)
+                    }
+                    "requires" => {
+                        for requirement in value.split(',').filter(|r| !r.trim().is_empty()) {
+                            let requirement = Requirement::parse(requirement, node.line)?;
+                            metadata.requires.push(requirement)
+                        }
+                    }
+                    _ => {
+                        return Err(MetadataError::new(
+                            node.line,
+                            &format!(
+                                "unknown key '{key}', the keys are: {}",
+                                META_KEYS.join(", ")
+                            ),
+                        ))
+                    }
+                }
+            }
+
+            Ok(metadata)
+        }
+
+        /// every reason the template can't run here (an old tmplt or a missing program),
+        /// all of them are returned so they can be fixed at once
+        pub fn check(&self) -> Vec<String> {
+            let mut problems = vec![];
+
+            if let Some(min) = &self.min_tmplt_version {
+                if *min > Version::current() {
+                    problems.push(format!(
+                        "the template needs tmplt {min} or newer, this is tmplt {}",
+                        Version::current()
+                    ))
+                }
+            }
+            for requirement in self.requires.iter() {
+                if let Err(problem) = requirement.check() {
+                    problems.push(problem)
+                }
+            }
+
+            problems
+        }
+    }
+
+    impl Requirement {
+        /// `go`, `node>=18` or `node >= 18`
+        pub fn parse(text: &str, line: usize) -> Result<Self, MetadataError> {
+            let text = text.trim();
+            let (program, min_version) = match text.split_once(">=") {
+                Some((program, version)) => {
+                    let version = Version::parse(version.trim()).ok_or_else(|| {
+                        MetadataError::new(
+                            line,
+                            &format!("'{}' isn't a version, i.e 'node>=18'", version.trim()),
+                        )
+                    })?;
+                    (program.trim(), Some(version))
+                }
+                None => (text, None),
+            };
+
+            let is_program = !program.is_empty()
+                && !program.contains(char::is_whitespace)
+                && !program.contains(['<', '>', '=']);
+            if !is_program {
+                return Err(MetadataError::new(
+                    line,
+                    &format!("'{text}' isn't a requirement, the requirements are written like 'go' or 'node>=18'"),
+                ));
+            }
+
+            Ok(Requirement {
+                program: program.to_string(),
+                min_version,
+                line,
+            })
+        }
+
+        /// where the program is installed, or why it doesn't meet the requirement
+        pub fn check(&self) -> Result<PathBuf, String> {
+            let path = find_program(&self.program).ok_or(format!(
+                "'{}' (required at line {}) isn't installed, it wasn't found in PATH",
+                self.program, self.line
+            ))?;
+
+            let min = match &self.min_version {
+                Some(min) => min,
+                None => return Ok(path),
+            };
+            match program_version(&path, VERSION_TIMEOUT) {
+                Some(version) if version >= *min => Ok(path),
+                Some(version) => Err(format!(
+                    "'{}' {min} or newer is required (at line {}), found {version} at {}",
+                    self.program,
+                    self.line,
+                    path.display()
+                )),
+                None => Err(format!(
+                    "'{}' {min} or newer is required (at line {}), unable to tell the version of {}",
+                    self.program,
+                    self.line,
+                    path.display()
+                )),
+            }
+        }
+    }
+
+    impl fmt::Display for Requirement {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.min_version {
+                Some(min) => write!(f, "{}>={min}", self.program),
+                None => write!(f, "{}", self.program),
+            }
+        }
+    }
+
+    impl Version {
+        /// `1.2.3` or `v1.2.3`
+        pub fn parse(text: &str) -> Option<Self> {
+            let text = text.strip_prefix('v').unwrap_or(text);
+            let numbers = text
+                .split('.')
+                .map(|n| n.parse::<u64>().ok())
+                .collect::<Option<Vec<u64>>>()?;
+            Some(Version(numbers))
+        }
+
+        /// the first version written in the text, i.e `1.21.3` in `go version go1.21.3 linux/amd64`,
+        /// a dotted number is preferred over a plain one
+        pub fn find(text: &str) -> Option<Self> {
+            let candidates = text
+                .split(|c: char| !c.is_ascii_digit() && c != '.')
+                .map(|word| word.trim_matches('.'))
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<&str>>();
+
+            candidates
+                .iter()
+                .find(|word| word.contains('.'))
+                .or(candidates.first())
+                .and_then(|word| Version::parse(word))
+        }
+
+        /// the version of this tmplt
+        pub fn current() -> Self {
+            Version::parse(env!("CARGO_PKG_VERSION")).unwrap_or(Version(vec![]))
+        }
+    }
+
+    impl Ord for Version {
+        fn cmp(&self, other: &Self) -> Ordering {
+            let len = self.0.len().max(other.0.len());
+            (0..len)
+                .map(|i| {
+                    let a = self.0.get(i).unwrap_or(&0);
+                    let b = other.0.get(i).unwrap_or(&0);
+                    a.cmp(b)
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        }
+    }
+
+    impl PartialOrd for Version {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl PartialEq for Version {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    impl Eq for Version {}
+
+    impl fmt::Display for Version {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let numbers = self
+                .0
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<String>>();
+            write!(f, "{}", numbers.join("."))
+        }
+    }
+
+    /// the executable of the program, searched in `PATH` like a shell would (with the
+    /// extensions of `PATHEXT` on windows), a program written as a path is used as it is
+    pub fn find_program(program: &str) -> Option<PathBuf> {
+        find_program_in(program, &env::var_os("PATH")?)
+    }
+
+    /// same as `find_program` with another `PATH`, i.e the one set in the `__ENV` section
+    pub fn find_program_in(program: &str, paths: &OsStr) -> Option<PathBuf> {
+        if program.contains('/') || program.contains('\\') {
+            let path = PathBuf::from(program);
+            return is_executable(&path).then_some(path);
+        }
+
+        let extensions = match cfg!(target_os = "windows") {
+            true => env::var("PATHEXT")
+                .unwrap_or(".COM;.EXE;.BAT;.CMD".to_string())
+                .split(';')
+                .map(|ext| ext.to_string())
+                .collect::<Vec<String>>(),
+            false => vec![],
+        };
+
+        env::split_paths(paths).find_map(|dir| {
+            std::iter::once(dir.join(program))
+                .chain(
+                    extensions
+                        .iter()
+                        .map(|ext| dir.join(format!("{program}{ext}"))),
+                )
+                .find(|path| is_executable(path))
+        })
+    }
+
+    #[cfg(unix)]
+    fn is_executable(path: &Path) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+
+        path.metadata()
+            .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    }
+
+    #[cfg(not(unix))]
+    fn is_executable(path: &Path) -> bool {
+        path.is_file()
+    }
+
+    /// the version the program prints with `--version` (or `version`, i.e `go version`), a program
+    /// that doesn't exit within the timeout (i.e it waits for input) is stopped and has no version
+    pub fn program_version(path: &Path, timeout: Duration) -> Option<Version> {
+        ["--version", "version"].iter().find_map(|arg| {
+            let mut child = Command::new(path)
+                .arg(arg)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .ok()?;
+
+            let start = Instant::now();
+            loop {
+                match child.try_wait() {
+                    Ok(Some(_)) => break,
+                    Ok(None) if start.elapsed() < timeout => thread::sleep(POLL_INTERVAL),
+                    _ => {
+                        terminate_child(&mut child);
+                        return None;
+                    }
+                }
+            }
+
+            let output = child.wait_with_output().ok()?;
+            if !output.status.success() {
+                return None;
+            }
+            Version::find(&String::from_utf8_lossy(&output.stdout))
+                .or_else(|| Version::find(&String::from_utf8_lossy(&output.stderr)))
+        })
+    }
+
+    /// the value without the quotes around it, `"a b"` and `'a b'` are `a b`
+    fn unquote(value: &str) -> String {
+        let quoted = value.len() >= 2
+            && ((value.starts_with('"') && value.ends_with('"'))
+                || (value.starts_with('\'') && value.ends_with('\'')));
+        match quoted {
+            true => value[1..value.len() - 1].to_string(),
+            false => value.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::metadata::*;
+
+    fn version(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn versions_are_dotted_numbers() {
+        assert_eq!(version("1.2.3").to_string(), "1.2.3");
+        assert_eq!(version("v18.17.0").to_string(), "18.17.0");
+        assert_eq!(version("18").to_string(), "18");
+
+        assert!(Version::parse("").is_none());
+        assert!(Version::parse("1.x").is_none());
+        assert!(Version::parse("1..2").is_none());
+        assert!(Version::parse("-1").is_none());
+    }
+
+    #[test]
+    fn the_version_is_found_in_what_a_program_prints() {
+        assert_eq!(Version::find("node v18.17.0"), Some(version("18.17.0")));
+        assert_eq!(
+            Version::find("go version go1.22.1 linux/amd64"),
+            Some(version("1.22.1"))
+        );
+        // a dotted number is preferred over a plain one
+        assert_eq!(
+            Version::find("gcc-12 (Debian 12.2.0-14) 12.2.0"),
+            Some(version("12.2.0"))
+        );
+        assert_eq!(Version::find("make 4"), Some(version("4")));
+        assert_eq!(Version::find("no version here"), None);
+    }
+
+    #[test]
+    fn versions_are_compared_number_by_number() {
+        assert!(version("1.9") < version("1.10"));
+        assert!(version("18.2.0") > version("18.1.9"));
+        assert!(version("2") > version("1.99.99"));
+        // a missing number counts as 0
+        assert_eq!(version("18"), version("18.0.0"));
+        assert!(version("18") < version("18.0.1"));
+    }
+
+    #[test]
+    fn requirements_are_a_program_and_an_optional_version() {
+        let requirement = Requirement::parse("go", 3).unwrap();
+        assert_eq!(requirement.program, "go");
+        assert!(requirement.min_version.is_none());
+        assert_eq!(requirement.line, 3);
+
+        for text in ["node>=18", " node >= 18 "] {
+            let requirement = Requirement::parse(text, 3).unwrap();
+            assert_eq!(requirement.program, "node");
+            assert_eq!(requirement.min_version, Some(version("18")));
+            assert_eq!(requirement.to_string(), "node>=18");
+        }
+
+        for text in ["", "node>=", "node>=x", "node<=18", "node=18", "my node"] {
+            assert!(Requirement::parse(text, 3).is_err(), "{text}");
+        }
+        let reason = Requirement::parse("node>=x", 3).unwrap_err().to_string();
+        assert_eq!(
+            reason,
+            "in the metadata section at line 3: 'x' isn't a version, i.e 'node>=18'"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_program_that_doesnt_print_its_version_in_time_is_stopped() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::{env, fs, time::Instant};
+
+        let dir = env::temp_dir().join(format!("tmplt-test-version-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let program = |name: &str, script: &str| {
+            let path = dir.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+
+        let tool = program("tool", "echo \"tool v2.3.1\"");
+        assert_eq!(
+            program_version(&tool, Duration::from_secs(5)),
+            Some(version("2.3.1"))
+        );
+
+        // the stdin is closed so `read` returns right away, then it never exits
+        let reader = program("reader", "read line\nexec sleep 10");
+        let start = Instant::now();
+        assert_eq!(program_version(&reader, Duration::from_millis(200)), None);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
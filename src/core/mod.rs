@@ -1,17 +1,21 @@
+mod formatter;
 mod interpreter;
+mod metadata;
 mod parser;
+mod preflight;
 mod syntax_tree;
 mod token;
 
 // short names
+pub use formatter::formatter::*;
 pub use interpreter::interpreter::*;
-pub use parser::parser::Parser;
+pub use metadata::metadata::*;
+pub use parser::parser::{Parser, SourceLine};
+pub use preflight::preflight::*;
 pub use syntax_tree::syntax_tree::*;
 pub use token::token as Tokens;
 
-use self::Tokens::UnknownSectionError;
-
-pub fn construct_tree(file_data: Vec<String>) -> Result<Tree, UnknownSectionError> {
+pub fn construct_tree(file_data: Vec<SourceLine>) -> Result<Tree, SyntaxError> {
     Tree::construct(file_data)
 }
 
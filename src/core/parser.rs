@@ -3,28 +3,54 @@
 /// which is then passed to the tokenizer
 pub mod parser {
     use std::io::ErrorKind::*;
+    use std::path::Path;
     use std::{fs, io::Read};
 
     pub struct Parser {
         pub ext: String,
     }
 
+    /// a logical line of the template file, a logical line can be written over multiple physical lines
+    /// by ending a line with a ` \`
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct SourceLine {
+        /// the line number (starting from 1) of the first physical line in the file
+        pub number: usize,
+        /// the line number of the last physical line, the same as `number` unless the line is continued
+        pub end: usize,
+        /// the indentation width of the first physical line, a tab counts as 4 spaces
+        pub indent: usize,
+        pub text: String,
+    }
+
+    impl SourceLine {
+        /// a line that opens a section (i.e `__CWD :`) or a scoped block (i.e `IN backend/ {`)
+        pub fn opens_block(&self) -> bool {
+            if self.text.starts_with("__") {
+                return true;
+            }
+
+            (self.text.starts_with("IN ") || self.text.starts_with("CD "))
+                && (self.text.ends_with(':') || self.text.ends_with('{'))
+        }
+    }
+
     impl Parser {
         pub fn new(ext: String) -> Self {
             Parser { ext }
         }
 
-        pub fn parse_file(&self, dest: String) -> Result<Vec<String>, std::io::Error> {
-            let replaced = dest.replace('\\', "/");
-            let files = replaced.split('/').collect::<Vec<&str>>();
-            let file_name = files.last().unwrap_or(&"");
-
-            let ext = file_name.split('.').collect::<Vec<&str>>();
-            if ext.len() != 2 {
-                // occurs when the file has more than one extension (i.e some.file.ext)
+        pub fn parse_file(&self, dest: String) -> Result<Vec<SourceLine>, std::io::Error> {
+            let has_ext = Path::new(&dest.replace('\\', "/"))
+                .extension()
+                .is_some_and(|ext| ext == self.ext.as_str());
+            if !has_ext {
                 return Err(std::io::Error::new(
                     InvalidInput,
-                    "wrong file format make sure the file follows this formate 'file_name.tmplt'",
+                    format!(
+                        "wrong file format make sure the file follows this formate 'file_name.{}'",
+                        self.ext
+                    ),
                 ));
             }
 
@@ -33,15 +59,324 @@ pub mod parser {
 
             file.read_to_string(&mut data)?;
 
-            let parsed_data = self.parse_data(data);
-            Ok(parsed_data)
+            self.parse_data(data)
+        }
+
+        /// the logical lines of the content of a template, without its comments
+        pub fn parse_data(&self, data: String) -> Result<Vec<SourceLine>, std::io::Error> {
+            let stripped = strip_comments(&data)?;
+
+            let mut lines: Vec<SourceLine> = vec![];
+            // set when the last physical line ended with a ` \`
+            let mut joined = false;
+
+            for (idx, (raw, original)) in stripped.split('\n').zip(data.split('\n')).enumerate() {
+                let text = raw.trim();
+                if text.is_empty() {
+                    // a blank line ends the line like in a shell, a comment doesn't
+                    if original.trim().is_empty() {
+                        joined = false
+                    }
+                    continue;
+                }
+                let indent = indentation(raw);
+
+                let continues = joined && text != "}";
+
+                let (text, ends_with_slash) = match text.strip_suffix('\\') {
+                    Some(rest) if rest.is_empty() || rest.ends_with(char::is_whitespace) => {
+                        (rest.trim_end(), true)
+                    }
+                    _ => (text, false),
+                };
+                joined = ends_with_slash;
+
+                match lines.last_mut() {
+                    Some(last) if continues => {
+                        last.end = idx + 1;
+                        if !text.is_empty() {
+                            last.text.push(' ');
+                            last.text.push_str(text)
+                        }
+                    }
+                    _ if text.is_empty() => (),
+                    _ => lines.push(SourceLine {
+                        number: idx + 1,
+                        end: idx + 1,
+                        indent,
+                        text: text.to_string(),
+                    }),
+                }
+            }
+
+            Ok(lines)
+        }
+    }
+
+    fn indentation(line: &str) -> usize {
+        line.chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum()
+    }
+
+    /// removes line comments (`// ...`) and block comments (`/* ... */`) from the file
+    ///
+    /// a comment only starts at the beginning of a line or after a whitespace and never inside
+    /// a quoted string, so things like `https://` or `'@/*'` are kept as they are. a quote is only
+    /// a quote if it is closed on the same line (the `'` of `don't` isn't one), and after a whitespace
+    /// `/*` only starts a block comment if a whitespace follows it and a `*/` closes it, so a glob
+    /// like `ls /*` is kept too.
+    /// newlines inside a block comment are kept so the line count of the file does not change
+    pub fn strip_comments(data: &str) -> Result<String, std::io::Error> {
+        let chars = data.chars().collect::<Vec<char>>();
+        let mut out = String::with_capacity(data.len());
+
+        let mut quote: Option<char> = None;
+        // the line where the currently opened block comment started
+        let mut block_start: Option<usize> = None;
+        let mut line: usize = 1;
+        let mut i: usize = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+
+            if c == '\n' {
+                // quotes are never multiline, an unclosed quote ends with the line
+                quote = None;
+                line += 1;
+                out.push(c);
+                i += 1;
+                continue;
+            }
+
+            if block_start.is_some() {
+                if c == '*' && next == Some('/') {
+                    block_start = None;
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+
+            if let Some(q) = quote {
+                if c == '\\' && q == '"' && next.is_some_and(|n| n != '\n') {
+                    out.push(c);
+                    out.push(next.unwrap());
+                    i += 2;
+                    continue;
+                }
+                if c == q {
+                    quote = None;
+                }
+                out.push(c);
+                i += 1;
+                continue;
+            }
+
+            let starts_word = i == 0 || chars[i - 1].is_whitespace();
+            let starts_line = chars[..i]
+                .iter()
+                .rev()
+                .take_while(|c| **c != '\n')
+                .all(|c| c.is_whitespace());
+            match (c, next) {
+                ('\'', _) | ('"', _) if closes_on_line(&chars[i + 1..], c) => {
+                    quote = Some(c);
+                    out.push(c);
+                    i += 1;
+                }
+                ('/', Some('/')) if starts_word => {
+                    // skips to the end of the line, the newline itself is kept
+                    while i < chars.len() && chars[i] != '\n' {
+                        i += 1;
+                    }
+                }
+                ('/', Some('*'))
+                    if starts_line
+                        || (starts_word
+                            && chars.get(i + 2).is_none_or(|c| c.is_whitespace())
+                            && closes_block(&chars[i + 2..])) =>
+                {
+                    block_start = Some(line);
+                    i += 2;
+                }
+                _ => {
+                    out.push(c);
+                    i += 1;
+                }
+            }
         }
 
-        fn parse_data(&self, data: String) -> Vec<String> {
-            data.split('\n')
-                .map(|val| val.trim().to_string())
-                .filter(|val| !val.starts_with("//") && !val.is_empty())
-                .collect::<Vec<String>>()
+        if let Some(start) = block_start {
+            return Err(std::io::Error::new(
+                InvalidData,
+                format!("unterminated block comment, the '/*' at line {start} is never closed with '*/'"),
+            ));
+        }
+
+        Ok(out)
+    }
+
+    /// if the quote is closed before the end of the line, a `\"` doesn't close a `"`
+    fn closes_on_line(rest: &[char], quote: char) -> bool {
+        let mut escaped = false;
+        for c in rest.iter().take_while(|c| **c != '\n') {
+            match c {
+                '\\' if quote == '"' && !escaped => escaped = true,
+                c if *c == quote && !escaped => return true,
+                _ => escaped = false,
+            }
         }
+        false
+    }
+
+    /// if a `*/` closes the block comment
+    fn closes_block(rest: &[char]) -> bool {
+        rest.windows(2).any(|pair| pair == ['*', '/'])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parser::*;
+
+    fn strip(data: &str) -> String {
+        strip_comments(data).unwrap()
+    }
+
+    fn parse(data: &str) -> Vec<(usize, String)> {
+        Parser::new("tmplt".to_string())
+            .parse_data(data.to_string())
+            .unwrap()
+            .into_iter()
+            .map(|line| (line.number, line.text))
+            .collect()
+    }
+
+    #[test]
+    fn indented_lines_arent_joined() {
+        let lines = parse("__SCRIPTS:\n    echo a\n        echo b\n");
+        assert_eq!(lines[1..], [(2, "echo a".into()), (3, "echo b".into())]);
+
+        let lines = parse("__CWD:\n    FOLDER /src\n        FILE /src/main.rs\n");
+        assert_eq!(
+            lines[1..],
+            [(2, "FOLDER /src".into()), (3, "FILE /src/main.rs".into())]
+        );
+    }
+
+    #[test]
+    fn a_trailing_backslash_joins_the_next_line() {
+        let lines =
+            parse("__SCRIPTS:\n    npm install \\\n        --save foo \\\n  bar\n    go build\n");
+        assert_eq!(
+            lines[1..],
+            [
+                (2, "npm install --save foo bar".into()),
+                (5, "go build".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn a_backslash_of_a_path_doesnt_join() {
+        let lines = parse("__CWD:\n    FOLDER app\\\n    FILE app\\main.rs\n");
+        assert_eq!(
+            lines[1..],
+            [(2, "FOLDER app\\".into()), (3, "FILE app\\main.rs".into())]
+        );
+    }
+
+    #[test]
+    fn line_numbers_skip_comments_and_blank_lines() {
+        let lines = parse(
+            "// top\n\n__CWD:\n    /* a\n    b */\n    FOLDER a/ \\\n    // b\n    FILE a/b\n",
+        );
+        assert_eq!(
+            lines,
+            [(3, "__CWD:".into()), (6, "FOLDER a/ FILE a/b".into())]
+        );
+    }
+
+    #[test]
+    fn a_joined_line_knows_its_last_line() {
+        let lines = Parser::new("tmplt".to_string())
+            .parse_data(
+                "__SCRIPTS:\n    npm install \\\n      // deps\n      --save foo\n    ls\n"
+                    .to_string(),
+            )
+            .unwrap();
+        assert_eq!(
+            lines
+                .iter()
+                .map(|line| (line.number, line.end))
+                .collect::<Vec<(usize, usize)>>(),
+            [(1, 1), (2, 4), (5, 5)]
+        );
+    }
+
+    #[test]
+    fn a_blank_line_ends_the_joined_line() {
+        let lines = parse("__SCRIPTS:\n    rm -rf build \\\n\n    go build\n");
+        assert_eq!(
+            lines[1..],
+            [(2, "rm -rf build".into()), (4, "go build".into())]
+        );
+    }
+
+    #[test]
+    fn trailing_comments_are_removed() {
+        assert_eq!(strip("FOLDER src/ // the sources"), "FOLDER src/ ");
+        assert_eq!(strip("// a whole line\nFILE a.txt"), "\nFILE a.txt");
+    }
+
+    #[test]
+    fn comments_need_a_whitespace_before_them() {
+        assert_eq!(
+            strip("git clone https://github.com/a/b"),
+            "git clone https://github.com/a/b"
+        );
+        assert_eq!(strip("echo a//b"), "echo a//b");
+    }
+
+    #[test]
+    fn quoted_comments_are_kept() {
+        assert_eq!(strip("echo '// not a comment'"), "echo '// not a comment'");
+        assert_eq!(strip("echo \"a \\\" // b\""), "echo \"a \\\" // b\"");
+        assert_eq!(strip("echo '@/*'"), "echo '@/*'");
+    }
+
+    #[test]
+    fn an_unclosed_quote_isnt_a_quote() {
+        assert_eq!(strip("echo don't  // note"), "echo don't  ");
+        assert_eq!(strip("echo 5\" // inches"), "echo 5\" ");
+        assert_eq!(strip("echo don't\necho 'a' // b"), "echo don't\necho 'a' ");
+    }
+
+    #[test]
+    fn block_comments_keep_the_line_count() {
+        assert_eq!(strip("/* a\nb */\nFILE a"), "\n\nFILE a");
+        assert_eq!(strip("    /* indented */ FILE a"), "     FILE a");
+        assert_eq!(
+            strip("echo one /* a\nb */ echo two"),
+            "echo one \n echo two"
+        );
+    }
+
+    #[test]
+    fn globs_arent_block_comments() {
+        assert_eq!(strip("ls /*"), "ls /*");
+        assert_eq!(strip("ls /* | wc -l"), "ls /* | wc -l");
+        assert_eq!(strip("cp src/* dest/"), "cp src/* dest/");
+        assert_eq!(strip("ls /*.txt /* the texts */"), "ls /*.txt ");
+    }
+
+    #[test]
+    fn an_unterminated_block_comment_is_an_error() {
+        let e = strip_comments("FILE a\n/* never closed\nFILE b").unwrap_err();
+        assert!(e.to_string().contains("line 2"), "{e}");
     }
 }
@@ -0,0 +1,547 @@
+/// the checks made once the template is interpreted and before any task runs, they look for
+/// everything that would make the run stop halfway (a missing program, a file to copy that doesn't
+/// exist, a directory that already exists...) and all the problems are reported at once
+pub mod preflight {
+    use std::env;
+    use std::fmt;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use crate::arg_parser::args::{ConflictPolicy, Options};
+    use crate::core::{find_program_in, ExecutableCommand, Interpreter, Metadata};
+
+    /// below this much free space a warning is given, even if nothing is copied
+    const LOW_DISK_SPACE: u64 = 10 * 1024 * 1024;
+
+    /// commands of `sh` that aren't programs, they can't be found in `PATH`
+    const SH_BUILTINS: &[&str] = &[
+        ".", ":", "[", "alias", "bg", "break", "case", "cd", "command", "continue", "echo", "eval",
+        "exec", "exit", "export", "false", "fg", "for", "getopts", "hash", "if", "jobs", "kill",
+        "printf", "pwd", "read", "readonly", "return", "set", "shift", "source", "test", "times",
+        "trap", "true", "type", "ulimit", "umask", "unalias", "unset", "until", "wait", "while",
+    ];
+
+    /// commands of `cmd` that aren't programs
+    const CMD_BUILTINS: &[&str] = &[
+        "assoc", "break", "call", "cd", "chdir", "cls", "color", "copy", "date", "del", "dir",
+        "echo", "endlocal", "erase", "exit", "for", "ftype", "goto", "if", "md", "mkdir", "mklink",
+        "move", "path", "pause", "popd", "prompt", "pushd", "rd", "ren", "rename", "rmdir", "set",
+        "setlocal", "shift", "start", "time", "title", "type", "ver", "verify", "vol",
+    ];
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Severity {
+        /// the template would fail, nothing runs
+        Error,
+        /// the template can run but the result may not be the expected one
+        Warning,
+    }
+
+    /// a problem found before running the template
+    #[derive(Clone, Debug)]
+    pub struct Problem {
+        pub severity: Severity,
+        /// the line of the template the problem comes from
+        pub line: Option<usize>,
+        pub message: String,
+    }
+
+    impl Problem {
+        pub fn error(line: Option<usize>, message: String) -> Self {
+            Problem {
+                severity: Severity::Error,
+                line,
+                message,
+            }
+        }
+
+        pub fn warning(line: Option<usize>, message: String) -> Self {
+            Problem {
+                severity: Severity::Warning,
+                line,
+                message,
+            }
+        }
+    }
+
+    impl fmt::Display for Problem {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.line {
+                Some(line) => write!(f, "line {line}: {}", self.message),
+                None => write!(f, "{}", self.message),
+            }
+        }
+    }
+
+    /// checks the interpreted template, the problems are in the order of the checks:
+    /// the requirements of `__META`, the programs of the scripts, the files to copy,
+    /// the paths that already exist, the directories to write in and the disk space
+    pub fn run_preflight(
+        interpreter: &Interpreter,
+        metadata: &Metadata,
+        options: &Options,
+    ) -> Vec<Problem> {
+        let mut problems = metadata
+            .check()
+            .into_iter()
+            .map(|problem| Problem::error(None, problem))
+            .collect::<Vec<Problem>>();
+
+        check_programs(interpreter, metadata, options, &mut problems);
+        check_copies(interpreter, &mut problems);
+        check_existing_paths(interpreter, options, &mut problems);
+        check_disk(interpreter, &mut problems);
+
+        problems
+    }
+
+    /// every program started by the scripts (and the `--shell`) has to be in `PATH`
+    fn check_programs(
+        interpreter: &Interpreter,
+        metadata: &Metadata,
+        options: &Options,
+        problems: &mut Vec<Problem>,
+    ) {
+        let path = env::var_os("PATH").unwrap_or_default();
+        let shell = options.get_shell_flag();
+
+        if let Some(shell) = &shell {
+            if find_program_in(&shell[0], &path).is_none() {
+                problems.push(Problem::error(
+                    None,
+                    format!(
+                        "the shell '{}' set with --shell isn't installed, it wasn't found in PATH",
+                        shell[0]
+                    ),
+                ))
+            }
+        }
+        let builtins = match cfg!(target_os = "windows") && shell.is_none() {
+            true => CMD_BUILTINS,
+            false => SH_BUILTINS,
+        };
+
+        // the missing programs and the lines using them
+        let mut missing: Vec<(String, Vec<usize>)> = vec![];
+        for command in interpreter.commands().iter().filter(|c| !c.demo) {
+            let program = command.command_name.as_str();
+            let is_builtin = builtins
+                .iter()
+                .any(|builtin| builtin.eq_ignore_ascii_case(program));
+            // a path may be created by the scripts before it is used, and shell syntax (i.e `$TOOL` or `a=b`)
+            // can't be checked without running it
+            let is_plain = !program.is_empty()
+                && !program.contains([
+                    '/', '\\', '$', '=', '(', '`', '\'', '"', '{', ';', '&', '|', '<', '>', '*',
+                    '?',
+                ]);
+            let required = metadata.requires.iter().any(|r| r.program == program);
+            if is_builtin || !is_plain || required {
+                continue;
+            }
+
+            // the `__ENV` section (or `ENV PATH=...`) can change where the programs are searched
+            let command_path = command
+                .env
+                .iter()
+                .rev()
+                .find(|(key, _)| key == "PATH")
+                .map(|(_, value)| value.into())
+                .unwrap_or(path.clone());
+            if find_program_in(program, &command_path).is_some() {
+                continue;
+            }
+
+            let line = command.line.unwrap_or_default();
+            match missing.iter_mut().find(|(name, _)| name == program) {
+                Some((_, lines)) => lines.push(line),
+                None => missing.push((program.to_string(), vec![line])),
+            }
+        }
+
+        for (program, lines) in missing {
+            let mut message = format!("'{program}' isn't installed, it wasn't found in PATH");
+            if lines.len() > 1 {
+                let others = lines[1..]
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect::<Vec<String>>();
+                message.push_str(&format!(" (also used at line {})", others.join(", ")));
+            }
+            problems.push(Problem::error(Some(lines[0]), message))
+        }
+    }
+
+    /// the files copied by `__DEMO` have to exist, a `DEFER` copy may copy something made by the scripts
+    fn check_copies(interpreter: &Interpreter, problems: &mut Vec<Problem>) {
+        for command in interpreter.commands().iter().filter(|c| c.demo) {
+            let args = command.args();
+            let sources = &args[..args.len().saturating_sub(1)];
+
+            for source in sources.iter().filter(|s| !Path::new(s).exists()) {
+                let problem = match command.defered {
+                    true => Problem::warning(
+                        command.line,
+                        format!("'{source}' doesn't exist yet, the scripts have to create it before it is copied"),
+                    ),
+                    false => Problem::error(
+                        command.line,
+                        format!("unable to copy '{source}', it doesn't exist"),
+                    ),
+                };
+                problems.push(problem)
+            }
+        }
+    }
+
+    /// the paths of `__CWD` shouldn't exist unless `--conflict` says what to do with them
+    fn check_existing_paths(
+        interpreter: &Interpreter,
+        options: &Options,
+        problems: &mut Vec<Problem>,
+    ) {
+        let conflict = *options.get_conflict_flag();
+
+        for action in interpreter.fs_actions() {
+            let path = action.path();
+            if !Path::new(path).exists() {
+                continue;
+            }
+
+            let problem = match (conflict, action.is_file()) {
+                (Some(ConflictPolicy::Error), _) => Problem::error(
+                    action.line(),
+                    format!("'{path}' already exists and --conflict=error doesn't allow it"),
+                ),
+                (None, false) => Problem::error(
+                    action.line(),
+                    format!("the directory '{path}' already exists, use --conflict=overwrite to use it as it is"),
+                ),
+                (None, true) => Problem::warning(
+                    action.line(),
+                    format!("the file '{path}' already exists and is going to be emptied, use --conflict=skip to keep it"),
+                ),
+                (Some(ConflictPolicy::Skip | ConflictPolicy::Overwrite), _) => continue,
+            };
+            problems.push(problem)
+        }
+    }
+
+    /// the directories the template writes in have to be writable and have enough space for the copies
+    fn check_disk(interpreter: &Interpreter, problems: &mut Vec<Problem>) {
+        // the closest existing directory of every path written, the line writing in it first
+        // and the size of the files copied in it
+        let mut roots: Vec<(PathBuf, usize, u64)> = vec![];
+        let mut add = |root: PathBuf, line: usize, size: u64| match roots
+            .iter_mut()
+            .find(|(dir, _, _)| *dir == root)
+        {
+            Some((_, _, total)) => *total += size,
+            None => roots.push((root, line, size)),
+        };
+
+        for action in interpreter.fs_actions() {
+            let line = action.line().unwrap_or_default();
+            add(existing_parent(Path::new(action.path())), line, 0)
+        }
+        for command in interpreter.commands().iter().filter(|c| c.demo) {
+            let args = command.args();
+            let dest = match args.last() {
+                Some(dest) => Path::new(dest),
+                None => continue,
+            };
+            let root = match dest.is_dir() {
+                true => dest.to_path_buf(),
+                false => existing_parent(dest),
+            };
+            let size = args[..args.len() - 1]
+                .iter()
+                .map(|source| disk_usage(Path::new(source)))
+                .sum();
+            add(root, command.line.unwrap_or_default(), size)
+        }
+
+        for (dir, line, needed) in roots {
+            if let Err(e) = probe_write(&dir) {
+                problems.push(Problem::error(
+                    Some(line),
+                    format!("unable to write in '{}': {e}", dir.display()),
+                ));
+                continue;
+            }
+
+            let free = match available_space(&dir) {
+                Some(free) => free,
+                None => continue,
+            };
+            if needed > free {
+                problems.push(Problem::error(
+                    Some(line),
+                    format!(
+                        "the copies need {} but only {} are free in '{}'",
+                        format_size(needed),
+                        format_size(free),
+                        dir.display()
+                    ),
+                ))
+            } else if free < LOW_DISK_SPACE {
+                problems.push(Problem::warning(
+                    Some(line),
+                    format!("only {} are free in '{}'", format_size(free), dir.display()),
+                ))
+            }
+        }
+    }
+
+    /// the closest directory containing the path that exists, the current directory for a relative path
+    fn existing_parent(path: &Path) -> PathBuf {
+        path.ancestors()
+            .skip(1)
+            .map(|dir| match dir.as_os_str().is_empty() {
+                true => Path::new("."),
+                false => dir,
+            })
+            .find(|dir| dir.is_dir())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    }
+
+    /// creates and removes a file in the directory
+    fn probe_write(dir: &Path) -> std::io::Result<()> {
+        let probe = dir.join(format!(".tmplt-preflight-{}", std::process::id()));
+        fs::File::create(&probe)?;
+        fs::remove_file(&probe)
+    }
+
+    /// the size of the file, or of everything in the directory (the links aren't followed)
+    fn disk_usage(path: &Path) -> u64 {
+        let meta = match fs::symlink_metadata(path) {
+            Ok(meta) => meta,
+            Err(_) => return 0,
+        };
+        if !meta.is_dir() {
+            return meta.len();
+        }
+
+        fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| disk_usage(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    /// the space left for the user in the file system of the directory
+    #[cfg(unix)]
+    #[allow(clippy::unnecessary_cast)]
+    fn available_space(dir: &Path) -> Option<u64> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        // the field types depend on the platform
+        Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+
+    /// not checked on windows
+    #[cfg(not(unix))]
+    fn available_space(_dir: &Path) -> Option<u64> {
+        None
+    }
+
+    fn format_size(bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+        let mut size = bytes as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        match unit {
+            0 => format!("{bytes} B"),
+            _ => format!("{size:.1} {}", UNITS[unit]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::preflight::*;
+    use crate::arg_parser::args::{ArgsParser, Options};
+    use crate::core::{Interpreter, Metadata, Parser, Tree};
+
+    /// an empty directory in the temp directory, removed first if it was left by another run
+    fn empty_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("tmplt-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn options(flags: &[&str]) -> Options {
+        let args = ["tmplt", "gen", "x.tmplt"]
+            .iter()
+            .chain(flags)
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>();
+        ArgsParser::new(args).parse_options(&[]).unwrap()
+    }
+
+    /// the problems of the template as `(severity, line, message)`
+    fn preflight(data: &str, options: &Options) -> Vec<(Severity, Option<usize>, String)> {
+        let lines = Parser::new("tmplt".to_string())
+            .parse_data(data.to_string())
+            .unwrap();
+        let mut interpreter = Interpreter::construct(Tree::construct(lines).unwrap());
+        interpreter.interpret().unwrap();
+
+        run_preflight(&interpreter, &Metadata::default(), options)
+            .into_iter()
+            .map(|problem| (problem.severity, problem.line, problem.message))
+            .collect()
+    }
+
+    fn display(path: &Path) -> String {
+        path.display().to_string()
+    }
+
+    #[test]
+    fn an_existing_directory_stops_the_run_and_an_existing_file_is_a_warning() {
+        let root = empty_dir("preflight-existing");
+        fs::create_dir(root.join("d")).unwrap();
+        fs::write(root.join("f.txt"), "kept?").unwrap();
+        let (d, f) = (display(&root.join("d")), display(&root.join("f.txt")));
+        let template = format!(
+            "__CWD:\n    FOLDER {d}\n    FILE {f}\n    FILE {}\n",
+            display(&root.join("new.txt"))
+        );
+
+        assert_eq!(
+            preflight(&template, &options(&[])),
+            vec![
+                (
+                    Severity::Error,
+                    Some(2),
+                    format!("the directory '{d}' already exists, use --conflict=overwrite to use it as it is")
+                ),
+                (
+                    Severity::Warning,
+                    Some(3),
+                    format!("the file '{f}' already exists and is going to be emptied, use --conflict=skip to keep it")
+                ),
+            ]
+        );
+
+        let problems = preflight(&template, &options(&["--conflict=error"]));
+        assert_eq!(
+            problems,
+            vec![
+                (
+                    Severity::Error,
+                    Some(2),
+                    format!("'{d}' already exists and --conflict=error doesn't allow it")
+                ),
+                (
+                    Severity::Error,
+                    Some(3),
+                    format!("'{f}' already exists and --conflict=error doesn't allow it")
+                ),
+            ]
+        );
+
+        // the policy says what to do with them
+        assert_eq!(
+            preflight(&template, &options(&["--conflict=overwrite"])),
+            vec![]
+        );
+        assert_eq!(preflight(&template, &options(&["--conflict=skip"])), vec![]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_missing_copy_source_stops_the_run_unless_the_copy_is_defered() {
+        let root = empty_dir("preflight-copies");
+        fs::write(root.join("present.txt"), "copied").unwrap();
+        let (missing, later) = (
+            display(&root.join("missing.txt")),
+            display(&root.join("later.txt")),
+        );
+        let template = format!(
+            "__DEMO:\n    {missing} COPY_INTO {}\n    DEFER {later} COPY_INTO {}\n    {} COPY_INTO {}\n",
+            display(&root.join("a.txt")),
+            display(&root.join("b.txt")),
+            display(&root.join("present.txt")),
+            display(&root.join("c.txt")),
+        );
+
+        assert_eq!(
+            preflight(&template, &options(&[])),
+            vec![
+                (
+                    Severity::Error,
+                    Some(2),
+                    format!("unable to copy '{missing}', it doesn't exist")
+                ),
+                (
+                    Severity::Warning,
+                    Some(3),
+                    format!("'{later}' doesn't exist yet, the scripts have to create it before it is copied")
+                ),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn the_directories_written_in_are_probed() {
+        let root = empty_dir("preflight-writable");
+        let template = format!(
+            "__CWD:\n    FOLDER {}\n    FILE {}\n",
+            display(&root.join("out")),
+            display(&root.join("out").join("main.c"))
+        );
+
+        // the closest existing directory is probed, and the probe is removed
+        let problems = preflight(&template, &options(&[]));
+        assert!(
+            problems
+                .iter()
+                .all(|(_, _, m)| !m.starts_with("unable to write")),
+            "{problems:?}"
+        );
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 0);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            // root can write anywhere
+            if unsafe { libc::geteuid() } != 0 {
+                fs::set_permissions(&root, fs::Permissions::from_mode(0o555)).unwrap();
+                let problems = preflight(&template, &options(&[]));
+                fs::set_permissions(&root, fs::Permissions::from_mode(0o755)).unwrap();
+
+                let (severity, line, message) = &problems[0];
+                assert_eq!((*severity, *line), (Severity::Error, Some(2)));
+                assert!(
+                    message.starts_with(&format!("unable to write in '{}'", display(&root))),
+                    "{message}"
+                );
+            }
+        }
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 pub mod syntax_tree {
     use core::fmt;
 
+    use crate::core::SourceLine;
     use crate::core::Tokens::*;
 
     /// the expected Tree should have three branches and every branch may have
@@ -11,7 +12,7 @@ pub mod syntax_tree {
     /// node: a line (for example 'DEFER npm i' is a node)
     #[derive(Clone, Debug)]
     pub struct Tree {
-        _file_vec: Vec<String>,
+        _file_vec: Vec<SourceLine>,
 
         pub branches: Vec<Branch>,
     }
@@ -23,7 +24,7 @@ pub mod syntax_tree {
     /// Note : the Branch struct act as a sections sperator so it will help to contribute task to implement asynchronous task execution
     #[derive(Clone, Debug)]
     pub struct Branch {
-        _section_vec: Vec<String>,
+        _section_vec: Vec<SourceLine>,
 
         pub section_kind: SectionIdentity,
         pub nodes: Vec<Node>,
@@ -39,35 +40,226 @@ pub mod syntax_tree {
 
         pub current_token: Token,
         pub text: String,
+        /// the line number in the template file, used for error messages
+        pub line: usize,
+        /// the last line of the node in the template file, it differs from `line` for a line continued with ` \`
+        pub end_line: usize,
+        /// the directories of the `IN dir` blocks this node is declared in, from the outermost to the innermost
+        pub scope: Vec<String>,
+    }
+
+    /// an error found while constructing the syntax tree, i.e an unknown section or an unclosed block
+    #[derive(Clone, Debug)]
+    pub struct SyntaxError {
+        line: usize,
+        reason: String,
+    }
+
+    impl SyntaxError {
+        pub fn new(line: usize, reason: &str) -> Self {
+            SyntaxError {
+                line,
+                reason: reason.to_string(),
+            }
+        }
+    }
+
+    impl SyntaxError {
+        pub fn line(&self) -> usize {
+            self.line
+        }
+
+        pub fn reason(&self) -> &str {
+            &self.reason
+        }
+    }
+
+    impl fmt::Display for SyntaxError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "syntax error at line {}: {}", self.line, self.reason)
+        }
+    }
+
+    /// how a section or a scoped block is opened, `:` blocks end with the indentation (or the next section)
+    /// and `{` blocks end with a line containing only `}`
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Opener {
+        Colon,
+        Brace,
+        /// a section header without `:` (i.e `__CWD`), behaves like `:`
+        Bare,
+    }
+
+    impl Opener {
+        fn from_suffix(text: &str) -> Option<Self> {
+            match text {
+                ":" => Some(Opener::Colon),
+                "{" => Some(Opener::Brace),
+                "" => Some(Opener::Bare),
+                _ => None,
+            }
+        }
+    }
+
+    /// parses a section header, `__CWD:`, `__CWD :`, `__CWD {` and `__CWD` are all valid
+    fn section_header(line: &SourceLine) -> Result<Option<(SectionIdentity, Opener)>, SyntaxError> {
+        if !line.text.starts_with("__") {
+            return Ok(None);
+        }
+
+        let name_end = line
+            .text
+            .find(|c: char| c.is_whitespace() || c == ':' || c == '{')
+            .unwrap_or(line.text.len());
+        let (name, rest) = line.text.split_at(name_end);
+
+        let sect = SectionIdentity::from(name.to_string());
+        if let Err(e) = sect.unknown(name.to_string(), line.number) {
+            return Err(SyntaxError::new(line.number, e.to_string().as_str()));
+        }
+
+        match Opener::from_suffix(rest.trim()) {
+            Some(opener) => Ok(Some((sect, opener))),
+            None => Err(SyntaxError::new(
+                line.number,
+                format!(
+                    "unexpected '{}' after the section name, a section is declared like '{name} :' or '{name} {{'",
+                    rest.trim()
+                )
+                .as_str(),
+            )),
+        }
+    }
+
+    /// parses a scoped block header, `IN backend/:`, `IN backend/ :` and `IN backend/ {` are all valid
+    /// and `CD` can be used instead of `IN`
+    fn scope_header(line: &SourceLine) -> Option<(String, Opener)> {
+        let rest = line
+            .text
+            .strip_prefix("IN ")
+            .or_else(|| line.text.strip_prefix("CD "))?
+            .trim();
+
+        let (path, opener) = if let Some(path) = rest.strip_suffix(':') {
+            (path.trim(), Opener::Colon)
+        } else if let Some(path) = rest.strip_suffix('{') {
+            (path.trim(), Opener::Brace)
+        } else {
+            return None;
+        };
+
+        if path.is_empty() || path.contains(char::is_whitespace) {
+            return None;
+        }
+
+        Some((path.to_string(), opener))
+    }
+
+    /// returns the index of the `}` closing the block opened at `open`
+    fn closing_brace(lines: &[SourceLine], open: usize) -> Result<usize, SyntaxError> {
+        let mut depth: usize = 0;
+
+        for (idx, line) in lines.iter().enumerate().skip(open + 1) {
+            if line.text == "}" {
+                if depth == 0 {
+                    return Ok(idx);
+                }
+                depth -= 1;
+            } else if line.text.ends_with('{') && line.opens_block() {
+                depth += 1;
+            }
+        }
+
+        Err(SyntaxError::new(
+            lines[open].number,
+            "this block is never closed, add a line containing only '}'",
+        ))
     }
 
     impl Tree {
-        pub fn construct(file_data: Vec<String>) -> Result<Self, UnknownSectionError> {
+        pub fn construct(file_data: Vec<SourceLine>) -> Result<Self, SyntaxError> {
+            let (tree, errors) = Tree::construct_all(file_data);
+            match errors.into_iter().next() {
+                Some(e) => Err(e),
+                None => Ok(tree),
+            }
+        }
+
+        /// same as `construct` but goes on after an error, the section with the error is left out
+        /// of the tree and the construction continues with the next section
+        pub fn construct_all(file_data: Vec<SourceLine>) -> (Self, Vec<SyntaxError>) {
             let mut branches: Vec<Branch> = Vec::with_capacity(3);
+            let mut errors: Vec<SyntaxError> = vec![];
+            let mut idx: usize = 0;
 
-            for (idx, line) in file_data.iter().enumerate() {
-                if line.starts_with("__") {
-                    let sect_name = line.split(' ').collect::<Vec<&str>>()[0];
+            // the next line declaring a section
+            let next_section = |from: usize| {
+                file_data
+                    .iter()
+                    .skip(from)
+                    .position(|val| val.text.starts_with("__"))
+                    .map(|pos| from + pos)
+                    .unwrap_or(file_data.len())
+            };
 
-                    let lines = file_data
-                        .iter()
-                        .skip(idx + 1)
-                        .take_while(|val| !val.starts_with("__"))
-                        .map(|v| v.to_string())
-                        .collect::<Vec<String>>();
-
-                    let sect = SectionIdentity::from(sect_name.to_string());
-                    match sect.unknown(sect_name.to_string()) {
-                        Ok(_) => branches.push(self::Branch::construct(lines, sect)),
-                        Err(e) => return Err(e),
+            while idx < file_data.len() {
+                let line = &file_data[idx];
+                let header = section_header(line).and_then(|header| {
+                    header.ok_or(SyntaxError::new(
+                        line.number,
+                        format!("'{}' is outside of any section", line.text).as_str(),
+                    ))
+                });
+                let (sect, opener) = match header {
+                    Ok(header) => header,
+                    Err(e) => {
+                        errors.push(e);
+                        idx = next_section(idx + 1);
+                        continue;
+                    }
+                };
+
+                let (body, next) = match opener {
+                    Opener::Brace => match closing_brace(&file_data, idx) {
+                        Ok(close) => (&file_data[idx + 1..close], close + 1),
+                        Err(e) => {
+                            errors.push(e);
+                            idx = next_section(idx + 1);
+                            continue;
+                        }
+                    },
+                    Opener::Colon | Opener::Bare => {
+                        let end = file_data
+                            .iter()
+                            .skip(idx + 1)
+                            .position(|val| val.text.starts_with("__"))
+                            .map(|pos| idx + 1 + pos)
+                            .unwrap_or(file_data.len());
+                        (&file_data[idx + 1..end], end)
+                    }
+                };
+
+                idx = next;
+                let branch = match Branch::construct(body.to_vec(), sect) {
+                    Ok(branch) => branch,
+                    Err(e) => {
+                        errors.push(e);
+                        continue;
                     }
+                };
+
+                // a section that is declared more than once is merged into the first one
+                match branches.iter_mut().find(|b| b.section_kind == sect) {
+                    Some(existing) => existing.merge(branch),
+                    None => branches.push(branch),
                 }
             }
 
-            Ok(Tree {
+            let tree = Tree {
                 _file_vec: file_data,
                 branches,
-            })
+            };
+            (tree, errors)
         }
     }
 
@@ -83,7 +275,10 @@ pub mod syntax_tree {
                     // formats for every nodes a text displaying the line
                     let text = nodes
                         .iter()
-                        .map(|n| n.words.join(" "))
+                        .map(|n| match n.scope.is_empty() {
+                            true => n.words.join(" "),
+                            false => format!("(IN {}) {}", n.scope.join(" > "), n.words.join(" ")),
+                        })
                         .collect::<Vec<String>>()
                         .join("\n        --- ");
 
@@ -104,22 +299,105 @@ pub mod syntax_tree {
     }
 
     impl Branch {
-        pub fn construct(section: Vec<String>, section_kind: SectionIdentity) -> Self {
-            let nodes = section
-                .iter()
-                .map(|v| Node::new(v.to_string()))
-                .collect::<Vec<Node>>();
+        pub fn construct(
+            section: Vec<SourceLine>,
+            section_kind: SectionIdentity,
+        ) -> Result<Self, SyntaxError> {
+            let nodes = Branch::scoped_nodes(&section, &[], section_kind)?;
 
-            Branch {
+            Ok(Branch {
                 _section_vec: section,
                 section_kind,
                 nodes,
+            })
+        }
+
+        /// appends the lines and nodes of another branch of the same kind
+        pub fn merge(&mut self, mut other: Branch) {
+            self._section_vec.append(&mut other._section_vec);
+            self.nodes.append(&mut other.nodes);
+        }
+
+        /// creates the nodes of a block, the lines inside an `IN dir` block get the dir added to their scope
+        fn scoped_nodes(
+            lines: &[SourceLine],
+            scope: &[String],
+            section_kind: SectionIdentity,
+        ) -> Result<Vec<Node>, SyntaxError> {
+            let mut nodes: Vec<Node> = vec![];
+            let mut idx: usize = 0;
+
+            while idx < lines.len() {
+                let line = &lines[idx];
+
+                if line.text == "}" {
+                    return Err(SyntaxError::new(
+                        line.number,
+                        "found a '}' that doesn't close any block",
+                    ));
+                }
+
+                if line.text.starts_with("__") {
+                    return Err(SyntaxError::new(
+                        line.number,
+                        "sections can not be declared inside another section",
+                    ));
+                }
+
+                let (path, opener) = match scope_header(line) {
+                    Some(header) => header,
+                    None => {
+                        let mut node = Node::new(line.text.clone(), line.number);
+                        node.end_line = line.end;
+                        node.scope = scope.to_vec();
+                        nodes.push(node);
+                        idx += 1;
+                        continue;
+                    }
+                };
+
+                if section_kind == SectionIdentity::Variables {
+                    return Err(SyntaxError::new(
+                        line.number,
+                        "'IN' blocks can not be used in the variables section",
+                    ));
+                }
+                if section_kind == SectionIdentity::Metadata {
+                    return Err(SyntaxError::new(
+                        line.number,
+                        "'IN' blocks can not be used in the metadata section",
+                    ));
+                }
+
+                let (body, next) = match opener {
+                    Opener::Brace => {
+                        let close = closing_brace(lines, idx)?;
+                        (&lines[idx + 1..close], close + 1)
+                    }
+                    _ => {
+                        let end = lines
+                            .iter()
+                            .skip(idx + 1)
+                            .position(|val| val.indent <= line.indent)
+                            .map(|pos| idx + 1 + pos)
+                            .unwrap_or(lines.len());
+                        (&lines[idx + 1..end], end)
+                    }
+                };
+
+                let mut inner_scope = scope.to_vec();
+                inner_scope.push(path);
+                nodes.append(&mut Branch::scoped_nodes(body, &inner_scope, section_kind)?);
+
+                idx = next;
             }
+
+            Ok(nodes)
         }
     }
 
     impl Node {
-        pub fn new(line: String) -> Self {
+        pub fn new(line: String, line_number: usize) -> Self {
             let first_keyword = line.split(' ').next().unwrap().to_string();
 
             let tkn = Token::from(first_keyword.clone());
@@ -133,6 +411,9 @@ pub mod syntax_tree {
                 curent_iteration: 0,
                 current_token: tkn,
                 text: first_keyword,
+                line: line_number,
+                end_line: line_number,
+                scope: vec![],
             }
         }
 
@@ -176,3 +457,128 @@ pub mod syntax_tree {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::syntax_tree::*;
+    use crate::core::Parser;
+    use crate::core::Tokens::SectionIdentity;
+
+    fn tree(data: &str) -> Result<Tree, SyntaxError> {
+        let lines = Parser::new("tmplt".to_string())
+            .parse_data(data.to_string())
+            .unwrap();
+        Tree::construct(lines)
+    }
+
+    /// the lines of the section as `scope | words`
+    fn nodes(tree: &Tree, kind: SectionIdentity) -> Vec<String> {
+        let branch = tree
+            .branches
+            .iter()
+            .find(|branch| branch.section_kind == kind)
+            .unwrap();
+        branch
+            .nodes
+            .iter()
+            .map(|node| format!("{} | {}", node.scope.join(">"), node.get_words().join(" ")))
+            .collect()
+    }
+
+    #[test]
+    fn every_section_header_is_valid() {
+        for header in ["__CWD:", "__CWD :", "__CWD {", "__CWD"] {
+            let close = if header.ends_with('{') { "}\n" } else { "" };
+            let tree = tree(&format!("{header}\n    FOLDER a/\n{close}")).unwrap();
+            assert_eq!(
+                nodes(&tree, SectionIdentity::CurrentWorkingDirectory),
+                [" | FOLDER a/"],
+                "{header}"
+            );
+        }
+    }
+
+    #[test]
+    fn braces_dont_depend_on_the_indentation() {
+        let tree =
+            tree("__CWD {\nFOLDER a/\n  FILE a/b\n}\n__SCRIPTS {\n    echo hi\n}\n").unwrap();
+        assert_eq!(
+            nodes(&tree, SectionIdentity::CurrentWorkingDirectory),
+            [" | FOLDER a/", " | FILE a/b"]
+        );
+        assert_eq!(nodes(&tree, SectionIdentity::Scripts), [" | echo hi"]);
+    }
+
+    #[test]
+    fn a_section_declared_twice_is_merged() {
+        let tree =
+            tree("__CWD:\n    FOLDER a/\n__SCRIPTS:\n    ls\n__CWD {\n    FOLDER b/\n}\n").unwrap();
+        assert_eq!(tree.branches.len(), 2);
+        assert_eq!(
+            nodes(&tree, SectionIdentity::CurrentWorkingDirectory),
+            [" | FOLDER a/", " | FOLDER b/"]
+        );
+    }
+
+    #[test]
+    fn in_blocks_can_be_nested() {
+        let tree = tree(
+            "__CWD:\n    IN a/:\n        FOLDER b/\n        IN b/ {\n            FILE c\n        }\n        FILE d\n    FILE e\n",
+        )
+        .unwrap();
+        assert_eq!(
+            nodes(&tree, SectionIdentity::CurrentWorkingDirectory),
+            [
+                "a/ | FOLDER b/",
+                "a/>b/ | FILE c",
+                "a/ | FILE d",
+                " | FILE e"
+            ]
+        );
+    }
+
+    #[test]
+    fn cd_is_an_alias_of_in() {
+        let tree = tree("__SCRIPTS:\n    CD web/ :\n        npm i\n").unwrap();
+        assert_eq!(nodes(&tree, SectionIdentity::Scripts), ["web/ | npm i"]);
+    }
+
+    #[test]
+    fn a_line_outside_of_any_section_is_an_error() {
+        let e = tree("FOLDER a/\n__CWD:\n    FOLDER b/\n").unwrap_err();
+        assert_eq!(e.line(), 1);
+        assert!(e.reason().contains("outside of any section"), "{e}");
+    }
+
+    #[test]
+    fn unclosed_and_extra_braces_are_errors() {
+        let e = tree("__CWD {\n    FOLDER a/\n").unwrap_err();
+        assert_eq!(e.line(), 1);
+        assert!(e.reason().contains("never closed"), "{e}");
+
+        let e = tree("__CWD:\n    FOLDER a/\n    }\n").unwrap_err();
+        assert_eq!(e.line(), 3);
+    }
+
+    #[test]
+    fn in_blocks_arent_allowed_in_the_variables_section() {
+        let e = tree("__VAR:\n    IN a/:\n        x = 1\n").unwrap_err();
+        assert_eq!(e.line(), 2);
+    }
+
+    #[test]
+    fn construct_all_goes_on_after_an_error() {
+        let lines = Parser::new("tmplt".to_string())
+            .parse_data("__NOPE:\n    x\n__CWD:\n    FOLDER a/\n__SCRIPTS {\n    ls\n".to_string())
+            .unwrap();
+        let (tree, errors) = Tree::construct_all(lines);
+        assert_eq!(
+            errors.iter().map(|e| e.line()).collect::<Vec<usize>>(),
+            [1, 5]
+        );
+        assert_eq!(
+            nodes(&tree, SectionIdentity::CurrentWorkingDirectory),
+            [" | FOLDER a/"]
+        );
+    }
+}
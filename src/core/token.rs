@@ -13,6 +13,20 @@ pub mod token {
         CopyAction,
         Assign,
         DeferAction,
+        /// `IN dir` or `CD dir`, runs a script (or a block of lines) inside a directory
+        WorkingDir,
+        /// `ENV KEY=value`, sets environment variables for a single script
+        SetEnv,
+        /// `TIMEOUT 60s`, stops a script that runs longer than the duration
+        Timeout,
+        /// `RETRY 3`, runs a failed script again
+        Retry,
+        /// `BACKOFF 2s`, the time waited before retrying a script
+        Backoff,
+        /// `INTERACTIVE`, runs a script in the terminal so it can ask questions, nothing else runs meanwhile
+        Interactive,
+        /// `HEAVY`, a script that uses a lot of resources, only a few of them run at the same time
+        Heavy,
         Arg(String),
         /// used mainly for storing texts and scripts in the current moment but may have multiple uses (i.e ordered commands)
         /// Note that this is the first ever version so I wont go crazy with it
@@ -25,17 +39,25 @@ pub mod token {
         Demostration,
         Scripts,
         Variables,
+        Environment,
+        /// `__META`, what the template is for and what it needs to run, nothing in it is executed
+        Metadata,
         UnknownSection,
     }
 
     #[derive(Clone, Debug)]
     pub struct UnknownSectionError {
         at: String,
+        line: usize,
     }
 
     impl fmt::Display for UnknownSectionError {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "Unknown section name : '{}' ", self.at)
+            write!(
+                f,
+                "Unknown section name : '{}' at line {}",
+                self.at, self.line
+            )
         }
     }
 
@@ -46,6 +68,13 @@ pub mod token {
                 "FILE" => DeclareFile,
                 "COPY" | "COPY_INTO" => CopyAction,
                 "DEFER" => DeferAction,
+                "IN" | "CD" => WorkingDir,
+                "ENV" => SetEnv,
+                "TIMEOUT" => Timeout,
+                "RETRY" => Retry,
+                "BACKOFF" => Backoff,
+                "INTERACTIVE" => Interactive,
+                "HEAVY" => Heavy,
                 "=" => Assign,
                 str if str.starts_with('#') => Arg(str.to_string()),
                 &_ => Skip,
@@ -53,6 +82,28 @@ pub mod token {
         }
     }
 
+    impl Token {
+        /// how the keyword is written when the template is formatted (i.e `FOLDER` for `FLDR`),
+        /// `None` if it isn't a keyword
+        pub fn keyword(&self) -> Option<&'static str> {
+            match self {
+                DeclareFolder => Some("FOLDER"),
+                DeclareFile => Some("FILE"),
+                CopyAction => Some("COPY_INTO"),
+                Assign => Some("="),
+                DeferAction => Some("DEFER"),
+                WorkingDir => Some("IN"),
+                SetEnv => Some("ENV"),
+                Timeout => Some("TIMEOUT"),
+                Retry => Some("RETRY"),
+                Backoff => Some("BACKOFF"),
+                Interactive => Some("INTERACTIVE"),
+                Heavy => Some("HEAVY"),
+                Arg(_) | Skip => None,
+            }
+        }
+    }
+
     impl From<String> for SectionIdentity {
         fn from(value: String) -> Self {
             match value.as_str() {
@@ -60,6 +111,8 @@ pub mod token {
                 "__DEMO" | "__EXAMPLES" => Demostration,
                 "__SCRIPTS" | "__CMD" => Scripts,
                 "__VAR" | "__VARIABLES" | "__ARGS" => Variables,
+                "__ENV" | "__ENVIRONMENT" => Environment,
+                "__META" | "__METADATA" => Metadata,
 
                 &_ => UnknownSection,
             }
@@ -67,9 +120,22 @@ pub mod token {
     }
 
     impl SectionIdentity {
-        pub fn unknown(&self, at: String) -> Result<(), UnknownSectionError> {
+        /// the name of the section, without its aliases (i.e `__SCRIPTS` for `__CMD`)
+        pub fn name(&self) -> &'static str {
+            match self {
+                CurrentWorkingDirectory => "__CWD",
+                Demostration => "__DEMO",
+                Scripts => "__SCRIPTS",
+                Variables => "__VAR",
+                Environment => "__ENV",
+                Metadata => "__META",
+                UnknownSection => "__UNKNOWN",
+            }
+        }
+
+        pub fn unknown(&self, at: String, line: usize) -> Result<(), UnknownSectionError> {
             match self {
-                UnknownSection => Err(UnknownSectionError { at }),
+                UnknownSection => Err(UnknownSectionError { at, line }),
                 _ => Ok(()),
             }
         }
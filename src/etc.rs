@@ -0,0 +1,47 @@
+//! the files tmplt ships with, they are compiled in so the binary works wherever it is installed.
+//! a file with the same name in `$TMPLT_ETC_DIR` or in the `etc` directory next to the binary
+//! (`<exe>/../etc`, the layout of the installer) is used instead.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub const HELP: EtcFile = EtcFile {
+    name: "help.txt",
+    embedded: include_str!("../etc/help.txt"),
+};
+
+pub const DEFAULT_TEMPLATE: EtcFile = EtcFile {
+    name: "default.tmplt",
+    embedded: include_str!("../etc/default.tmplt"),
+};
+
+pub struct EtcFile {
+    name: &'static str,
+    embedded: &'static str,
+}
+
+impl EtcFile {
+    /// the content of the file from the first override directory that has it, the embedded one otherwise
+    pub fn read(&self) -> String {
+        override_dirs()
+            .into_iter()
+            .find_map(|dir| fs::read_to_string(dir.join(self.name)).ok())
+            .unwrap_or_else(|| self.embedded.to_string())
+    }
+}
+
+/// the directories searched for a file overriding an embedded one, in order
+fn override_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Some(dir) = env::var_os("TMPLT_ETC_DIR").filter(|dir| !dir.is_empty()) {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.parent()?.join("etc")))
+    {
+        dirs.push(dir);
+    }
+    dirs
+}
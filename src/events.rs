@@ -0,0 +1,248 @@
+//! the events printed with `--output=json`, one JSON object per line, see the README for the schema
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::logger::console::{self, Stream};
+use crate::report::{json_option, json_string, summary_fields, task_fields};
+use crate::tasks::{Task, TaskOutcome};
+
+/// the version of the events, bumped when a field changes meaning or gets removed
+pub const EVENTS_VERSION: u32 = 1;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// the `time_ms` of every event is counted from here
+static RUN_START: OnceLock<Instant> = OnceLock::new();
+
+/// prints the events instead of the usual output
+pub fn enable() {
+    RUN_START.get_or_init(Instant::now);
+    ENABLED.store(true, Ordering::SeqCst)
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+fn emit(event: &str, fields: &[String]) {
+    if !enabled() {
+        return;
+    }
+
+    let time = RUN_START
+        .get()
+        .map_or(0, |start| start.elapsed().as_millis());
+    console::write_line(Stream::Stdout, &event_line(event, time, fields))
+}
+
+/// the JSON object of an event, the fields are the `"key": value` pairs after the common ones
+fn event_line(event: &str, time: u128, fields: &[String]) -> String {
+    let mut line = format!(
+        "{{\"version\": {EVENTS_VERSION}, \"event\": {}, \"time_ms\": {time}",
+        json_string(event)
+    );
+    for field in fields {
+        line.push_str(", ");
+        line.push_str(field);
+    }
+    line.push('}');
+    line
+}
+
+/// every task that is going to run, in the order they are given to the workers
+pub fn plan(template: &str, tasks: &[&Task]) {
+    emit("plan", &plan_fields(template, tasks))
+}
+
+fn plan_fields(template: &str, tasks: &[&Task]) -> Vec<String> {
+    let tasks = tasks
+        .iter()
+        .map(|task| {
+            let depends_on = task
+                .dependency_ids()
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>();
+            format!(
+                "{{\"id\": {}, \"kind\": {}, \"label\": {}, \"line\": {}, \"line_end\": {}, \"defered\": {}, \"depends_on\": [{}]}}",
+                task.id(),
+                json_string(task.kind()),
+                json_string(task.label()),
+                json_option(task.line()),
+                json_option(task.line_end()),
+                task.defered,
+                depends_on.join(", ")
+            )
+        })
+        .collect::<Vec<String>>();
+
+    vec![
+        format!("\"template\": {}", json_string(template)),
+        format!("\"tasks\": [{}]", tasks.join(", ")),
+    ]
+}
+
+pub fn task_started(task: &Task) {
+    emit("task_started", &task_started_fields(task))
+}
+
+fn task_started_fields(task: &Task) -> Vec<String> {
+    vec![
+        format!("\"id\": {}", task.id()),
+        format!("\"kind\": {}", json_string(task.kind())),
+        format!("\"label\": {}", json_string(task.label())),
+        format!("\"line\": {}", json_option(task.line())),
+        format!("\"line_end\": {}", json_option(task.line_end())),
+    ]
+}
+
+/// a line printed by a script
+pub fn task_output(id: usize, stream: Stream, text: &str) {
+    emit("task_output", &task_output_fields(id, stream, text))
+}
+
+fn task_output_fields(id: usize, stream: Stream, text: &str) -> Vec<String> {
+    let stream = match stream {
+        Stream::Stdout => "stdout",
+        Stream::Stderr => "stderr",
+    };
+
+    vec![
+        format!("\"id\": {id}"),
+        format!("\"stream\": {}", json_string(stream)),
+        format!("\"text\": {}", json_string(text)),
+    ]
+}
+
+/// sent for every task of the plan, even the ones that didn't run
+pub fn task_finished(outcome: &TaskOutcome) {
+    emit("task_finished", &[task_fields(outcome)])
+}
+
+/// the last event, `error` is set if the template couldn't be run at all
+pub fn run_finished(outcomes: &[TaskOutcome], duration: Duration, error: Option<&str>) {
+    emit(
+        "run_finished",
+        &run_finished_fields(outcomes, duration, error),
+    )
+}
+
+fn run_finished_fields(
+    outcomes: &[TaskOutcome],
+    duration: Duration,
+    error: Option<&str>,
+) -> Vec<String> {
+    let mut fields = summary_fields(outcomes, duration);
+    fields.push(format!(
+        "\"error\": {}",
+        json_option(error.map(json_string))
+    ));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::core::ExecutableCommand;
+    use crate::logger::console::Stream;
+    use crate::tasks::{TaskContext, TaskStatus};
+
+    /// a command that does nothing
+    #[derive(Clone)]
+    struct Noop(&'static str, Option<usize>, Option<usize>);
+
+    impl ExecutableCommand<String> for Noop {
+        fn _execute(&self, _context: &TaskContext) -> Result<String, String> {
+            Ok(String::new())
+        }
+
+        fn describe(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn line(&self) -> Option<usize> {
+            self.1
+        }
+
+        fn line_end(&self) -> Option<usize> {
+            self.2
+        }
+    }
+
+    #[test]
+    fn plan_lists_the_tasks_and_their_dependencies() {
+        let build = Task::new(Noop("go build", Some(5), Some(5)), false, 0, None);
+        let mut copy = Task::new(Noop("copy \"bin\"", None, None), true, 1, None);
+        copy.depends_on(&build);
+
+        assert_eq!(
+            event_line("plan", 0, &plan_fields("api.tmplt", &[&build, &copy])),
+            r#"{"version": 1, "event": "plan", "time_ms": 0, "template": "api.tmplt", "tasks": [{"id": 0, "kind": "command", "label": "go build", "line": 5, "line_end": 5, "defered": false, "depends_on": []}, {"id": 1, "kind": "command", "label": "copy \"bin\"", "line": null, "line_end": null, "defered": true, "depends_on": [0]}]}"#
+        );
+    }
+
+    #[test]
+    fn task_started_tells_which_task() {
+        // a script continued over lines 3 to 5
+        let task = Task::new(Noop("npm i --save foo", Some(3), Some(5)), false, 2, None);
+        assert_eq!(
+            event_line("task_started", 12, &task_started_fields(&task)),
+            r#"{"version": 1, "event": "task_started", "time_ms": 12, "id": 2, "kind": "command", "label": "npm i --save foo", "line": 3, "line_end": 5}"#
+        );
+    }
+
+    #[test]
+    fn task_output_has_the_stream_and_the_line() {
+        assert_eq!(
+            event_line(
+                "task_output",
+                40,
+                &task_output_fields(2, Stream::Stderr, "warn: \"x\"\t1")
+            ),
+            r#"{"version": 1, "event": "task_output", "time_ms": 40, "id": 2, "stream": "stderr", "text": "warn: \"x\"\t1"}"#
+        );
+        assert!(task_output_fields(0, Stream::Stdout, "")[1].contains("\"stdout\""));
+    }
+
+    #[test]
+    fn task_finished_has_the_outcome() {
+        let mut outcome = TaskOutcome::new(2, "npm i".to_string(), TaskStatus::Failed);
+        outcome.line = Some(3);
+        outcome.line_end = Some(4);
+        outcome.exit_code = Some(1);
+        outcome.duration = Duration::from_millis(80);
+        outcome.stderr = "ERR!".to_string();
+
+        assert_eq!(
+            event_line("task_finished", 90, &[task_fields(&outcome)]),
+            r#"{"version": 1, "event": "task_finished", "time_ms": 90, "id": 2, "kind": "command", "label": "npm i", "line": 3, "line_end": 4, "defered": false, "status": "failed", "exit_code": 1, "duration_ms": 80, "stdout": "", "stderr": "ERR!"}"#
+        );
+    }
+
+    #[test]
+    fn run_finished_has_the_counts_and_the_error() {
+        let outcomes = [
+            TaskOutcome::new(0, "a".to_string(), TaskStatus::Succeeded),
+            TaskOutcome::new(1, "b".to_string(), TaskStatus::Cancelled),
+        ];
+        assert_eq!(
+            event_line(
+                "run_finished",
+                100,
+                &run_finished_fields(&outcomes, Duration::from_millis(95), None)
+            ),
+            r#"{"version": 1, "event": "run_finished", "time_ms": 100, "duration_ms": 95, "succeeded": 1, "failed": 0, "skipped": 0, "cancelled": 1, "error": null}"#
+        );
+        assert_eq!(
+            event_line(
+                "run_finished",
+                1,
+                &run_finished_fields(&[], Duration::ZERO, Some("no such file"))
+            ),
+            r#"{"version": 1, "event": "run_finished", "time_ms": 1, "duration_ms": 0, "succeeded": 0, "failed": 0, "skipped": 0, "cancelled": 0, "error": "no such file"}"#
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! `tmplt fmt`, rewrites the templates in the canonical style (see `core::format_template`) so
+//! every template reads the same way, whoever wrote it.
+//!
+//! with `--check` nothing is written, the templates that aren't formatted are listed and it exits
+//! with 1, so it can run in the CI. it exits with 2 when a template can't be found.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::arg_parser::args::Options;
+use crate::core::format_template;
+use crate::logger::console::{self, Color, Stream};
+use crate::logger::writer::LogStatus;
+use crate::logprint;
+use crate::templates;
+
+/// `tmplt fmt [templates...]`, returns if every template is (or is now) formatted,
+/// an error means a template couldn't be found
+pub fn handle_fmt_command(args: &Options) -> Result<bool, String> {
+    let names = args.get_arguments();
+    let paths = match names.is_empty() {
+        true => templates::list_templates(args)
+            .into_iter()
+            .map(|template| template.path)
+            .collect::<Vec<PathBuf>>(),
+        false => names
+            .iter()
+            .map(|name| templates::resolve(name, args))
+            .collect::<Result<Vec<PathBuf>, String>>()?,
+    };
+    if paths.is_empty() {
+        return Err(
+            "no template to format, pass the templates or add some to the search path (see tmplt list)"
+                .to_string(),
+        );
+    }
+
+    let check = *args.get_format_check_flag();
+    let (mut changed, mut failed) = (0, 0);
+    for path in paths.iter() {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                failed += 1;
+                logprint!(LogStatus::Error, "unable to read {}: {e}", path.display());
+                continue;
+            }
+        };
+        let formatted = match format_template(&source) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                failed += 1;
+                logprint!(LogStatus::Error, "{}: {e}", path.display());
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        changed += 1;
+
+        if check {
+            // the first line that differs, so it is easy to find
+            let line = source
+                .lines()
+                .zip(formatted.lines())
+                .position(|(a, b)| a != b)
+                .unwrap_or(source.lines().count().min(formatted.lines().count()))
+                + 1;
+            let label = console::paint(Stream::Stdout, "isn't formatted", Color::Yellow);
+            console::write_line(
+                Stream::Stdout,
+                &format!("{}:{line}: {label}", path.display()),
+            );
+            continue;
+        }
+
+        match fs::write(path, formatted) {
+            Ok(()) => logprint!(LogStatus::Info, "formatted {}", path.display()),
+            Err(e) => {
+                failed += 1;
+                logprint!(LogStatus::Error, "unable to write {}: {e}", path.display())
+            }
+        }
+    }
+
+    match check {
+        true => logprint!(
+            LogStatus::Info,
+            "checked {} template(s): {changed} not formatted, {failed} failed",
+            paths.len()
+        ),
+        false => logprint!(
+            LogStatus::Info,
+            "formatted {changed} of {} template(s), {failed} failed",
+            paths.len()
+        ),
+    }
+    Ok(failed == 0 && (changed == 0 || !check))
+}
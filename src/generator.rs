@@ -1,93 +1,207 @@
-use crate::arg_parser::args::Options;
-use crate::core;
-use crate::logformat;
+use crate::arg_parser::args::{Options, OutputFormat};
+use crate::core::{self, run_preflight, Metadata, Problem, Severity};
+use crate::events;
+use crate::logger::progress;
 use crate::logger::writer::LogStatus;
-use crate::logger::writer::LogWriter;
+use crate::logger::writer::{log_enabled, LogWriter, RunEnd};
+use crate::logprint;
+use crate::report::{print_summary, summary_lines, write_report};
 use crate::tasks::OpArcMutex;
 use crate::tasks::TasksExecutor;
-use crate::tasks::{execute_batch, ExecutionError, TaskError, ThreadResult};
+use crate::tasks::{execute_batches, is_cancelled, set_heavy_limit, TaskOutcome, TaskStatus};
+use crate::templates;
 use crate::Parser;
+use std::time::Instant;
 
 fn execute(
-    mut outs: Vec<Result<ThreadResult, ExecutionError>>,
     executor: &mut TasksExecutor,
-) -> Result<(), ExecutionError> {
-    for task_batch in executor {
-        let out = execute_batch(task_batch);
-        outs.push(out)
-    }
-
-    let outs = outs
-        .into_iter()
-        .collect::<Result<Vec<ThreadResult>, ExecutionError>>();
+    jobs: usize,
+    logger: &OpArcMutex<LogWriter>,
+) -> Vec<TaskOutcome> {
+    let outcomes = execute_batches(executor, jobs);
 
-    let thread_results = match outs {
-        Ok(res) => res,
-        Err(e) => return Err(e),
-    };
+    for outcome in outcomes
+        .iter()
+        .filter(|o| !o.succeeded() && o.status != TaskStatus::Cancelled)
+    {
+        report_outcome(outcome, LogStatus::Error, logger)
+    }
+    outcomes
+}
 
-    let mut op_errors: Vec<Option<TaskError>> = vec![];
+/// prints the line of a task that didn't succeed, the log file also gets the end of its output
+/// since the output printed while it ran may be hidden by the log level
+fn report_outcome(outcome: &TaskOutcome, status: LogStatus, logger: &OpArcMutex<LogWriter>) {
+    logprint!(status.clone(), "{outcome}");
 
-    for thread in thread_results {
-        op_errors.push(thread.join().unwrap());
+    if let Some(logger) = logger {
+        let mut logger = logger.lock().unwrap();
+        logger.write(outcome.to_string(), status.clone());
+        for (stream, output) in [("stdout", &outcome.stdout), ("stderr", &outcome.stderr)] {
+            for line in output.lines() {
+                logger.write(format!("  [{stream}] {line}"), status.clone())
+            }
+        }
     }
+}
 
-    let _ = op_errors
+/// prints the tasks that didn't run (or got stopped) because the run was cancelled
+fn report_cancelled(outcomes: &[TaskOutcome], logger: &OpArcMutex<LogWriter>) {
+    let cancelled = outcomes
         .iter()
-        .map(|op| match op {
-            Some(err) => eprintln!("{err:?}"),
-            None => (),
-        })
-        .collect::<Vec<_>>();
-    Ok(())
+        .filter(|o| o.status == TaskStatus::Cancelled)
+        .collect::<Vec<&TaskOutcome>>();
+
+    let line = format!("run cancelled, {} task(s) were cancelled:", cancelled.len());
+    logprint!(LogStatus::Warning, "{line}");
+    if let Some(logger) = logger {
+        logger.lock().unwrap().write(line, LogStatus::Warning)
+    }
+    for outcome in cancelled {
+        report_outcome(outcome, LogStatus::Warning, logger)
+    }
 }
 
-pub fn generate(args: Options, logger: OpArcMutex<LogWriter>) {
+/// reads the template and creates the tasks, returns the template file and the executor
+fn load_template(
+    args: &Options,
+    logger: OpArcMutex<LogWriter>,
+) -> Result<(String, TasksExecutor), String> {
     let file_parser = Parser::new("tmplt".to_string());
     let file = match args.get_file() {
-        Some(file) => file,
-        None => {
-            return eprintln!("should've passed a file ( no file dedected that end with .tmplt )")
-        }
+        Some(file) => templates::resolve(&file, args)?.display().to_string(),
+        None => return Err(
+            "should've passed a template, a .tmplt file or the name of a template (see tmplt list)"
+                .to_string(),
+        ),
     };
 
-    let out = file_parser.parse_file(file);
-
-    let mut interpreter = match out {
-        Ok(vect) => {
-            let tree = match core::construct_tree(vect) {
-                Ok(t) => t,
-                Err(e) => {
-                    let err: String = LogStatus::Error.into();
-                    return println!("{}{e}, hint: when creating a section make sure to seperate the ':' from the section name", logformat!("", err));
-                }
-            };
-
-            let mut interpreter = core::construct_interpreter(tree);
-            match interpreter.interpret() {
-                Ok(interpreter) => interpreter.to_owned(),
-                Err(e) => return eprintln!("{:?}", e),
-            }
-        }
-        Err(e) => return eprint!("{}", e),
+    let vect = file_parser
+        .parse_file(file.clone())
+        .map_err(|e| e.to_string())?;
+    let tree = core::construct_tree(vect).map_err(|e| e.to_string())?;
+    logprint!(LogStatus::Debug, "syntax tree of {file}:\n{tree}");
+    let metadata = Metadata::from_tree(&tree).map_err(|e| e.to_string())?;
+
+    let mut interpreter = core::construct_interpreter(tree);
+    interpreter.override_variables(args.get_vars_flag());
+    let mut interpreter = match interpreter.interpret() {
+        Ok(interpreter) => interpreter.to_owned(),
+        Err(e) => return Err(format!("{e:?}")),
     };
+    logprint!(LogStatus::Debug, "interpreted {file}:\n{interpreter}");
 
-    let mut executor = match interpreter
-        .create_tasks_executor(args.get_batch_size_flag(), logger.clone())
-    {
-        Some(exe) => exe,
+    // nothing runs if the template would stop halfway
+    if !*args.get_no_preflight_flag() {
+        let problems = run_preflight(&interpreter, &metadata, args);
+        let (errors, warnings): (Vec<Problem>, Vec<Problem>) = problems
+            .into_iter()
+            .partition(|problem| problem.severity == Severity::Error);
+        for warning in warnings {
+            logprint!(LogStatus::Warning, "{warning}")
+        }
+        if !errors.is_empty() {
+            let errors = errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<String>>();
+            return Err(format!(
+                "nothing was run, the preflight checks found {} problem(s):\n  - {}\nfix them or use --no-preflight to run anyway",
+                errors.len(),
+                errors.join("\n  - ")
+            ));
+        }
+    }
+
+    match interpreter.create_tasks_executor(args, logger) {
+        Some(exe) => Ok((file, exe)),
         None => panic!("wtf just happened, paniced while creating tasks (this shouldn't happen)"),
+    }
+}
+
+/// prints (and logs) the error that stopped the run
+fn abort(
+    error: &str,
+    outcomes: &[TaskOutcome],
+    start: Instant,
+    logger: &OpArcMutex<LogWriter>,
+) -> RunEnd {
+    progress::finish();
+    logprint!(LogStatus::Error, "{}", error.trim_end());
+    if let Some(logger) = logger {
+        let mut logger = logger.lock().unwrap();
+        logger.write(error.trim_end().to_string(), LogStatus::Error)
+    }
+    events::run_finished(outcomes, start.elapsed(), Some(error.trim_end()));
+    RunEnd::Aborted
+}
+
+/// runs the template, returns how the run ended so it can be recorded in the logs
+pub fn generate(args: Options, logger: OpArcMutex<LogWriter>) -> RunEnd {
+    if *args.get_output_flag() == OutputFormat::Json {
+        events::enable()
+    }
+    let start = Instant::now();
+
+    let (file, mut executor) = match load_template(&args, logger.clone()) {
+        Ok(loaded) => loaded,
+        Err(e) => return abort(&e, &[], start, &logger),
     };
+    events::plan(&file, &executor.planned_tasks());
+    // the events already tell the progress, and `-q` asks for the problems only
+    let show_progress = !events::enabled() && log_enabled(&LogStatus::Info);
+    if show_progress {
+        progress::start(executor.planned_tasks().len())
+    }
+
+    let jobs = args.get_jobs_flag();
+    set_heavy_limit(args.get_heavy_jobs_flag());
+    let mut outcomes: Vec<TaskOutcome> = vec![];
 
     // first iteration
-    match execute(vec![], &mut executor) {
-        Ok(()) => (),
-        Err(e) => return eprintln!("{e:?}"),
-    }
+    outcomes.append(&mut execute(&mut executor, jobs, &logger));
 
     // second iteration
-    match execute(vec![], executor.toggle_switch()) {
-        Ok(()) => (),
-        Err(e) => eprintln!("{e:?}"),
+    outcomes.append(&mut execute(executor.start_defered_phase(), jobs, &logger));
+
+    if show_progress {
+        progress::finish()
+    }
+
+    if is_cancelled() {
+        report_cancelled(&outcomes, &logger)
+    }
+
+    outcomes.sort_by_key(|o| o.id);
+    let duration = start.elapsed();
+    if !events::enabled() {
+        print_summary(&outcomes, duration);
+    }
+    if let Some(logger) = &logger {
+        let mut logger = logger.lock().unwrap();
+        for (status, line) in summary_lines(&outcomes, duration) {
+            logger.write(line, status)
+        }
+    }
+
+    if let Some(path) = args.get_report_flag() {
+        match write_report(path, &outcomes, duration) {
+            Ok(_) if events::enabled() => (),
+            Ok(_) => logprint!(LogStatus::Info, "report written to {path}"),
+            Err(e) => logprint!(
+                LogStatus::Error,
+                "unable to write the report to {path} : {e}"
+            ),
+        }
+    }
+
+    events::run_finished(&outcomes, duration, None);
+
+    if is_cancelled() {
+        return RunEnd::Cancelled;
+    }
+    match outcomes.iter().all(|o| o.succeeded()) {
+        true => RunEnd::Completed,
+        false => RunEnd::Failed,
     }
 }
@@ -1,23 +1,67 @@
-use std::{fs, io::Read, path::Path};
+use crate::arg_parser::args::{find_command, CommandSpec, COMMANDS};
+use crate::etc;
+use crate::logger::console::{self, Stream};
+use crate::{logger::writer::LogStatus, logprint};
 
-use crate::{logformat, logger::writer::LogStatus};
-
-pub fn handle_help_command(parent: &Path) {
-    let mut help_file = match fs::File::open(format!("{}\\..\\etc\\help.txt", parent.display())) {
-        Ok(f) => f,
-        Err(e) => {
-            let error: String = LogStatus::Error.into();
-            return eprintln!("{}{e}", logformat!("", error));
-        }
-    };
-    let mut string = String::new();
-    match help_file.read_to_string(&mut string) {
-        Ok(_) => (),
-        Err(e) => {
-            let error: String = LogStatus::Error.into();
-            return eprintln!("{}{e}", logformat!("", error));
-        }
+/// prints the help text and the commands, or the usage and the flags of the command
+pub fn handle_help_command(topic: Option<&str>) {
+    let topic = match topic {
+        Some(topic) => topic,
+        None => return print_general_help(),
     };
 
-    println!("{}", string);
+    match find_command(topic) {
+        Some(command) => print_command_help(command),
+        None => logprint!(
+            LogStatus::Error,
+            "unknown command '{topic}', use tmplt help to see all the commands"
+        ),
+    }
+}
+
+fn print_general_help() {
+    let mut help = etc::HELP.read().trim_end().to_string();
+
+    help.push_str("\n\ncommands:\n");
+    for command in COMMANDS {
+        help.push_str(&format!(
+            "    {:<20} {}\n",
+            names(command.name, command.aliases),
+            command.about
+        ));
+    }
+    help.push_str("\nuse 'tmplt help <command>' to see the flags of a command");
+
+    console::write_line(Stream::Stdout, &help)
+}
+
+fn print_command_help(command: &CommandSpec) {
+    let mut help = format!("{}\n\nusage: {}\n", command.about, command.usage);
+    if !command.aliases.is_empty() {
+        help.push_str(&format!("aliases: {}\n", command.aliases.join(", ")));
+    }
+
+    if !command.flags.is_empty() {
+        help.push_str("\nflags:\n");
+    }
+    for flag in command.flags {
+        let name = match flag.value {
+            Some(value) => format!("{}={value}", names(flag.name, flag.aliases)),
+            None => names(flag.name, flag.aliases),
+        };
+        help.push_str(&format!(
+            "    {name}\n        {} (default: {})\n",
+            flag.about, flag.default
+        ));
+    }
+
+    console::write_line(Stream::Stdout, help.trim_end())
+}
+
+/// the name followed by its aliases, i.e `generate (gen, g)`
+fn names(name: &str, aliases: &[&str]) -> String {
+    match aliases.is_empty() {
+        true => name.to_string(),
+        false => format!("{name} ({})", aliases.join(", ")),
+    }
 }
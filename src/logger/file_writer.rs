@@ -1,14 +1,30 @@
 pub mod writer {
-    use chrono::{DateTime, Local};
+    use chrono::{DateTime, Local, NaiveDate};
     use std::env;
-    use std::{fs::File, io::Write, path::Path};
+    use std::panic;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::{Arc, Mutex, OnceLock, TryLockError};
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::Path,
+    };
 
-    #[derive(Clone, Debug)]
+    use crate::report::json_string;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
     pub enum LogStatus {
         Info,
         Warning,
         Error,
         ForcedAction,
+        /// what tmplt understood from the template and why it does what it does
+        Debug,
+        /// every step of the run, i.e which worker runs which task
+        Trace,
     }
 
     impl From<LogStatus> for String {
@@ -18,6 +34,142 @@ pub mod writer {
                 LogStatus::Warning => "WARNING".to_string(),
                 LogStatus::Error => "ERROR".to_string(),
                 LogStatus::ForcedAction => "FORCED".to_string(),
+                LogStatus::Debug => "DEBUG".to_string(),
+                LogStatus::Trace => "TRACE".to_string(),
+            }
+        }
+    }
+
+    impl LogStatus {
+        /// the lower the level the more important the status is
+        fn level(&self) -> u8 {
+            match self {
+                LogStatus::Error => 1,
+                LogStatus::Warning | LogStatus::ForcedAction => 2,
+                LogStatus::Info => 3,
+                LogStatus::Debug => 4,
+                LogStatus::Trace => 5,
+            }
+        }
+
+        /// the status from its name as written in `TMPLT_LOG` (i.e `debug`)
+        pub fn from_name(name: &str) -> Option<LogStatus> {
+            match name.trim().to_lowercase().as_str() {
+                "error" => Some(LogStatus::Error),
+                "warn" | "warning" => Some(LogStatus::Warning),
+                "info" => Some(LogStatus::Info),
+                "debug" => Some(LogStatus::Debug),
+                "trace" => Some(LogStatus::Trace),
+                _ => None,
+            }
+        }
+    }
+
+    /// the least important status that is printed and logged, `INFO` by default
+    static LOG_LEVEL: AtomicU8 = AtomicU8::new(3);
+
+    pub fn set_log_level(status: LogStatus) {
+        LOG_LEVEL.store(status.level(), Ordering::SeqCst)
+    }
+
+    /// if lines with this status are printed and logged
+    pub fn log_enabled(status: &LogStatus) -> bool {
+        status.level() <= LOG_LEVEL.load(Ordering::SeqCst)
+    }
+
+    /// how each line of the log file is written
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum LogFormat {
+        /// `[12:30:00 | INFO] > text`
+        #[default]
+        Text,
+        /// `{"time": "...", "status": "INFO", "message": "text"}`
+        Json,
+    }
+
+    /// which of the old log files are kept when a new one is created
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Retention {
+        /// keeps the newest N log files
+        Count(usize),
+        /// keeps the log files of the last N days
+        Days(u64),
+    }
+
+    impl Default for Retention {
+        fn default() -> Self {
+            Retention::Count(30)
+        }
+    }
+
+    /// where and how the logs are written
+    #[derive(Clone, Debug, Default)]
+    pub struct LogOptions {
+        /// the log file, a new file in `default_log_dir()` when not set
+        pub file: Option<PathBuf>,
+        /// the directory of the log files, `default_log_dir()` when not set
+        pub dir: Option<PathBuf>,
+        pub format: LogFormat,
+        /// only applies to the logs in the logs directory
+        pub retention: Retention,
+    }
+
+    /// the directory the logs are saved in, following the conventions of the platform:
+    /// - linux and others: `$XDG_STATE_HOME/tmplt/logs` (`~/.local/state/tmplt/logs` by default)
+    /// - macos: `~/Library/Logs/tmplt`
+    /// - windows: `%LOCALAPPDATA%\tmplt\logs`
+    pub fn default_log_dir() -> PathBuf {
+        let env_dir = |key: &str| {
+            env::var_os(key)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        let home = env_dir("HOME").or_else(|| env_dir("USERPROFILE"));
+
+        let dir = if cfg!(target_os = "windows") {
+            env_dir("LOCALAPPDATA").map(|dir| dir.join("tmplt").join("logs"))
+        } else if cfg!(target_os = "macos") {
+            home.map(|home| home.join("Library").join("Logs").join("tmplt"))
+        } else {
+            env_dir("XDG_STATE_HOME")
+                .or_else(|| home.map(|home| home.join(".local").join("state")))
+                .map(|dir| dir.join("tmplt").join("logs"))
+        };
+
+        dir.unwrap_or_else(|| env::temp_dir().join("tmplt").join("logs"))
+    }
+
+    /// how many lines are kept in memory before they are written to the log file
+    const FLUSH_LINES: usize = 64;
+
+    /// how often the lines kept in memory are written to the log file, even if there are only a few
+    const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// how the run ended, written as the last line of the log file
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum RunEnd {
+        Completed,
+        /// every task was given a chance to run but some of them failed
+        Failed,
+        /// stopped by an error before all the tasks ran (i.e the template couldn't be read)
+        Aborted,
+        /// cancelled by a ctrl-c, the running tasks were stopped
+        Cancelled,
+        /// exited right away by a second ctrl-c
+        Interrupted,
+        /// tmplt panicked
+        Crashed,
+    }
+
+    impl RunEnd {
+        fn describe(&self) -> &'static str {
+            match self {
+                RunEnd::Completed => "run completed",
+                RunEnd::Failed => "run completed, some tasks failed",
+                RunEnd::Aborted => "run stopped by an error, it didn't complete",
+                RunEnd::Cancelled => "run cancelled, it didn't complete",
+                RunEnd::Interrupted => "run interrupted, it didn't complete",
+                RunEnd::Crashed => "tmplt crashed, the run didn't complete",
             }
         }
     }
@@ -26,20 +178,29 @@ pub mod writer {
     pub struct LogWriter {
         timestamp: DateTime<Local>,
         log_prefix: String,
+        options: LogOptions,
 
+        /// the lines that aren't written to the file yet
         lines_buffer: Vec<String>,
         file_buf: Option<File>,
+        /// set once the trailer is written, nothing is logged after it
+        finished: bool,
     }
 
+    /// the logger of the run, flushed by the panic hook and the ctrl-c handler
+    static ACTIVE_LOGGER: OnceLock<Arc<Mutex<LogWriter>>> = OnceLock::new();
+
     impl LogWriter {
-        pub fn new(logs_prefix: String) -> Self {
+        pub fn new(logs_prefix: String, options: LogOptions) -> Self {
             let time = Local::now();
 
             LogWriter {
                 timestamp: time,
                 log_prefix: logs_prefix,
+                options,
                 file_buf: None,
                 lines_buffer: vec![],
+                finished: false,
             }
         }
 
@@ -51,8 +212,8 @@ pub mod writer {
             self.log_prefix.clone()
         }
 
-        pub fn initialize_logger(logs_prefix: String) -> Self {
-            let mut logger = LogWriter::new(logs_prefix);
+        pub fn initialize_logger(logs_prefix: String, options: LogOptions) -> Self {
+            let mut logger = LogWriter::new(logs_prefix, options);
 
             logger.init_file();
 
@@ -60,56 +221,114 @@ pub mod writer {
         }
 
         pub fn write(&mut self, text: String, status: LogStatus) {
-            let line = format!(
-                "[{} | {}] > {}",
-                self.log_prefix,
-                <LogStatus as Into<String>>::into(status),
-                text
-            );
+            if !log_enabled(&status) {
+                return;
+            }
+            self.push_line(text, status)
+        }
+
+        /// writes the line even if the log level hides it, used for the output of a command that
+        /// doesn't fit in memory so it is only lost when nothing is logged
+        pub fn spill(&mut self, text: String, status: LogStatus) {
+            self.push_line(text, status)
+        }
 
-            self.lines_buffer.push(line)
+        fn push_line(&mut self, text: String, status: LogStatus) {
+            if self.finished {
+                return;
+            }
+
+            // errors are written right away, they are what's looked for after a crash
+            let urgent = status == LogStatus::Error;
+            let line = self.format_line(status.into(), &text);
+            self.lines_buffer.push(line);
+
+            if urgent || self.lines_buffer.len() >= FLUSH_LINES {
+                self.flush()
+            }
+        }
+
+        fn format_line(&self, status: String, text: &str) -> String {
+            match self.options.format {
+                LogFormat::Text => format!("[{} | {}] > {}", self.log_prefix, status, text),
+                LogFormat::Json => format!(
+                    "{{\"time\": {}, \"status\": {}, \"message\": {}}}",
+                    json_string(&Local::now().to_rfc3339()),
+                    json_string(&status),
+                    json_string(text)
+                ),
+            }
         }
 
-        pub fn write_to_file(&self) {
+        /// writes the lines kept in memory to the log file
+        pub fn flush(&mut self) {
+            if self.lines_buffer.is_empty() {
+                return;
+            }
+
+            let mut text = self.lines_buffer.join("\n");
+            text.push('\n');
+            self.lines_buffer.clear();
+
+            // the error was already printed when the file couldn't be created
             let mut file = match self.get_file() {
                 Some(f) => f,
-                None => return eprintln!("cannot find file"),
+                None => return,
             };
 
-            match file.write_all(self.lines_buffer.join("\n").as_bytes()) {
-                Ok(_) => (),
-                Err(e) => eprintln!("couldn't write to file, got error: {}", e),
+            if let Err(e) = file.write_all(text.as_bytes()).and_then(|_| file.flush()) {
+                eprintln!("couldn't write to the log file, got error: {}", e)
             }
         }
 
+        /// writes the trailer line telling how the run ended and flushes the log file
+        pub fn finish(&mut self, end: RunEnd) {
+            if self.finished {
+                return;
+            }
+
+            let line = self.format_line("END".to_string(), end.describe());
+            self.lines_buffer.push(line);
+            self.flush();
+            self.finished = true;
+        }
+
         fn init_file(&mut self) {
-            match self.file_buf {
-                Some(_) => (),
+            if self.file_buf.is_some() {
+                return;
+            }
+
+            let path = match self.options.file.clone() {
+                Some(path) => path,
                 None => {
-                    let exe_dir = env::current_exe().unwrap();
-                    let parent = exe_dir.parent().unwrap();
-                    let path = format!(
-                        "{}\\..\\logs\\{}.log",
-                        parent.display(),
-                        self.formated_timestamp(parent.display().to_string())
-                    );
-                    let file = match File::create(path){
-                        Ok(f) => f,
-                        Err(e) => return eprintln!("got err while creating log file, this error shouldn't appear, consider opening an issue in the github repository.\n Error: {}", e)
-                    };
+                    let dir = self.options.dir.clone().unwrap_or_else(default_log_dir);
+                    if let Err(e) = fs::create_dir_all(&dir) {
+                        return eprintln!(
+                            "unable to create the logs directory {} : {e}",
+                            dir.display()
+                        );
+                    }
+                    prune_logs(&dir, self.options.retention);
+                    dir.join(format!("{}.log", self.formated_timestamp(&dir)))
+                }
+            };
 
-                    self.file_buf = Some(file)
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                if let Err(e) = fs::create_dir_all(parent) {
+                    return eprintln!(
+                        "unable to create the logs directory {} : {e}",
+                        parent.display()
+                    );
                 }
             }
-        }
-
-        fn exists(&self, path: String) -> bool {
-            let is_exists = Path::new(&path).exists();
 
-            is_exists
+            match File::create(&path) {
+                Ok(file) => self.file_buf = Some(file),
+                Err(e) => eprintln!("unable to create the log file {} : {e}", path.display()),
+            }
         }
 
-        fn formated_timestamp(&self, parent: String) -> String {
+        fn formated_timestamp(&self, dir: &Path) -> String {
             let mut last_number: usize = 0;
 
             loop {
@@ -119,7 +338,7 @@ pub mod writer {
                     format!("{}", self.timestamp.format("%d-%m-%Y"))
                 };
 
-                if self.exists(format!("{parent}\\..\\logs\\{}.log", string)) {
+                if dir.join(format!("{string}.log")).exists() {
                     last_number += 1
                 } else {
                     break string;
@@ -127,4 +346,182 @@ pub mod writer {
             }
         }
     }
+
+    /// makes the logger the one of the run: its lines are flushed every `FLUSH_INTERVAL`
+    /// and by the panic hook, which also writes the trailer if the main thread panics
+    pub fn watch(logger: Arc<Mutex<LogWriter>>) {
+        if ACTIVE_LOGGER.set(logger.clone()).is_err() {
+            return;
+        }
+
+        thread::spawn(move || loop {
+            thread::sleep(FLUSH_INTERVAL);
+            match logger.lock() {
+                Ok(mut logger) if !logger.finished => logger.flush(),
+                _ => break,
+            }
+        });
+
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            // the panic of a task is caught and the run goes on, only the main thread crashes the run
+            let end = match thread::current().name() {
+                Some("main") => Some(RunEnd::Crashed),
+                _ => None,
+            };
+            with_active_logger(|logger| {
+                logger.write(format!("panicked: {info}"), LogStatus::Error);
+                if let Some(end) = end {
+                    logger.finish(end)
+                }
+            });
+            default_hook(info)
+        }));
+    }
+
+    /// writes the trailer of the logger of the run, if there is one
+    pub fn finish_active_logger(end: RunEnd) {
+        with_active_logger(|logger| logger.finish(end))
+    }
+
+    /// the panicking thread might hold the lock, so it isn't waited for
+    fn with_active_logger(f: impl FnOnce(&mut LogWriter)) {
+        let logger = match ACTIVE_LOGGER.get() {
+            Some(logger) => logger,
+            None => return,
+        };
+
+        match logger.try_lock() {
+            Ok(mut logger) => f(&mut logger),
+            Err(TryLockError::Poisoned(poisoned)) => f(&mut poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => (),
+        }
+    }
+
+    /// removes the old log files of the directory, the new log file isn't created yet
+    /// so one less file than the retention count is kept. only the files named like the ones
+    /// tmplt creates are touched, the directory could be shared with other programs
+    pub fn prune_logs(dir: &Path, retention: Retention) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        let mut logs = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| is_log_name(path))
+            .filter_map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+                Some((path, modified))
+            })
+            .collect::<Vec<(PathBuf, SystemTime)>>();
+        // the newest first
+        logs.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+
+        let expired = match retention {
+            Retention::Count(count) => logs
+                .into_iter()
+                .skip(count.saturating_sub(1))
+                .collect::<Vec<(PathBuf, SystemTime)>>(),
+            Retention::Days(days) => {
+                let max_age = Duration::from_secs(days.saturating_mul(24 * 60 * 60));
+                logs.into_iter()
+                    .filter(|(_, modified)| modified.elapsed().is_ok_and(|age| age > max_age))
+                    .collect()
+            }
+        };
+
+        for (path, _) in expired {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// `dd-mm-YYYY.log` or `dd-mm-YYYY_N.log`, the names given by `formated_timestamp`
+    fn is_log_name(path: &Path) -> bool {
+        if path.extension().is_none_or(|ext| ext != "log") {
+            return false;
+        }
+        let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(stem) => stem,
+            None => return false,
+        };
+
+        let (date, number) = stem.split_once('_').unwrap_or((stem, "1"));
+        date.len() == 10
+            && NaiveDate::parse_from_str(date, "%d-%m-%Y").is_ok()
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::writer::*;
+
+    /// an empty directory in the temp directory, removed first if it was left by another run
+    fn empty_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("tmplt-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn files(dir: &PathBuf) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn only_the_logs_of_tmplt_are_pruned() {
+        let dir = empty_dir("prune");
+        for name in [
+            "01-02-2024.log",
+            "01-02-2024_3.log",
+            "server.log",
+            "01-02-2024_x.log",
+            "31-31-2024.log",
+            "notes.txt",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        prune_logs(&dir, Retention::Count(1));
+        assert_eq!(
+            files(&dir),
+            vec![
+                "01-02-2024_x.log",
+                "31-31-2024.log",
+                "notes.txt",
+                "server.log"
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_newest_logs_are_kept() {
+        let dir = empty_dir("keep");
+        fs::write(dir.join("01-02-2024.log"), "").unwrap();
+        fs::write(dir.join("02-02-2024.log"), "").unwrap();
+        fs::write(dir.join("other.log"), "").unwrap();
+        // the modification times are compared, so the newest is written last
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(dir.join("01-02-2024.log"), "newer").unwrap();
+
+        prune_logs(&dir, Retention::Count(2));
+        assert_eq!(files(&dir), vec!["01-02-2024.log", "other.log"]);
+
+        prune_logs(&dir, Retention::Days(1));
+        assert_eq!(files(&dir), vec!["01-02-2024.log", "other.log"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,3 +1,7 @@
 pub mod file_writer;
+pub mod status_line;
+pub mod terminal;
 
 pub use file_writer::writer;
+pub use status_line::progress;
+pub use terminal::console;
@@ -0,0 +1,236 @@
+/// the progress of the run: how many tasks are done, which ones are running and for how long the
+/// run has been going. it is a line kept at the bottom of the terminal, when stderr isn't a
+/// terminal (i.e in a CI) a plain line is printed every `PLAIN_INTERVAL` instead
+pub mod progress {
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+
+    use crate::logger::console::{self, Color, Stream};
+    use crate::logger::writer::LogStatus;
+    use crate::logprint;
+    use crate::tasks::{TaskOutcome, TaskStatus};
+
+    /// how often the line at the bottom of the terminal is drawn again
+    const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+    /// how often the plain progress line is printed when stderr isn't a terminal
+    const PLAIN_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// how many running tasks are named in the progress line
+    const RUNNING_SHOWN: usize = 3;
+
+    struct Progress {
+        start: Instant,
+        total: usize,
+        succeeded: usize,
+        failed: usize,
+        skipped: usize,
+        /// the id and label of the running tasks
+        running: BTreeMap<usize, String>,
+        /// drawn at the bottom of the terminal or printed as plain lines
+        live: bool,
+        /// the number of interactive tasks using the terminal, nothing is drawn while they run
+        paused: usize,
+    }
+
+    static PROGRESS: Mutex<Option<Progress>> = Mutex::new(None);
+
+    static RUNNING: AtomicBool = AtomicBool::new(false);
+
+    static TICKER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+    fn with_progress(f: impl FnOnce(&mut Progress)) {
+        let mut progress = PROGRESS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(progress) = progress.as_mut() {
+            f(progress)
+        }
+    }
+
+    /// starts showing the progress of the run, `total` is the number of planned tasks
+    pub fn start(total: usize) {
+        let live = Stream::Stderr.is_terminal();
+        *PROGRESS.lock().unwrap_or_else(|e| e.into_inner()) = Some(Progress {
+            start: Instant::now(),
+            total,
+            succeeded: 0,
+            failed: 0,
+            skipped: 0,
+            running: BTreeMap::new(),
+            live,
+            paused: 0,
+        });
+        RUNNING.store(true, Ordering::SeqCst);
+
+        let ticker = thread::spawn(move || {
+            let interval = if live {
+                REDRAW_INTERVAL
+            } else {
+                PLAIN_INTERVAL
+            };
+            let mut last = Instant::now();
+            while RUNNING.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(50));
+                if last.elapsed() >= interval {
+                    last = Instant::now();
+                    with_progress(|progress| progress.show());
+                }
+            }
+        });
+        *TICKER.lock().unwrap_or_else(|e| e.into_inner()) = Some(ticker);
+    }
+
+    /// stops showing the progress and removes the line at the bottom of the terminal
+    pub fn finish() {
+        RUNNING.store(false, Ordering::SeqCst);
+        if let Some(ticker) = TICKER.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = ticker.join();
+        }
+
+        if let Some(progress) = PROGRESS.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            if progress.live {
+                console::set_status_line(None)
+            }
+        }
+    }
+
+    pub fn task_started(id: usize, label: &str) {
+        with_progress(|progress| {
+            progress.running.insert(id, label.to_string());
+            progress.show_live()
+        })
+    }
+
+    pub fn task_finished(outcome: &TaskOutcome) {
+        with_progress(|progress| {
+            progress.running.remove(&outcome.id);
+            match outcome.status {
+                TaskStatus::Succeeded => progress.succeeded += 1,
+                TaskStatus::Failed | TaskStatus::TimedOut(_) => progress.failed += 1,
+                _ => progress.skipped += 1,
+            }
+            progress.show_live()
+        })
+    }
+
+    /// hides the progress while an interactive task uses the terminal, until the guard is dropped
+    pub fn pause() -> PauseGuard {
+        with_progress(|progress| {
+            progress.paused += 1;
+            if progress.live {
+                console::set_status_line(None)
+            }
+        });
+        PauseGuard
+    }
+
+    pub struct PauseGuard;
+
+    impl Drop for PauseGuard {
+        fn drop(&mut self) {
+            with_progress(|progress| {
+                progress.paused = progress.paused.saturating_sub(1);
+                progress.show_live()
+            })
+        }
+    }
+
+    impl Progress {
+        fn done(&self) -> usize {
+            self.succeeded + self.failed + self.skipped
+        }
+
+        fn describe(&self) -> String {
+            // the elapsed time comes first so it isn't cut when the line is too long
+            let mut line = format!(
+                "[{}/{}] {:.1}s | {} succeeded, {} failed, {} skipped",
+                self.done(),
+                self.total,
+                self.start.elapsed().as_secs_f64(),
+                self.succeeded,
+                self.failed,
+                self.skipped
+            );
+
+            if !self.running.is_empty() {
+                let running = self
+                    .running
+                    .iter()
+                    .take(RUNNING_SHOWN)
+                    .map(|(id, label)| format!("task {id} ({label})"))
+                    .collect::<Vec<String>>();
+                line.push_str(&format!(" | running: {}", running.join(", ")));
+                if self.running.len() > RUNNING_SHOWN {
+                    line.push_str(&format!(" and {} more", self.running.len() - RUNNING_SHOWN));
+                }
+            }
+
+            line
+        }
+
+        /// draws the line at the bottom of the terminal, nothing when the progress isn't live
+        fn show_live(&self) {
+            if self.live && self.paused == 0 {
+                let line = truncate(&self.describe(), terminal_width());
+                console::set_status_line(Some(console::paint(Stream::Stderr, &line, Color::Cyan)))
+            }
+        }
+
+        fn show(&self) {
+            match self.live {
+                true => self.show_live(),
+                // only printed for runs long enough to need it
+                false if !self.running.is_empty() && self.paused == 0 => {
+                    logprint!(LogStatus::Info, "progress: {}", self.describe())
+                }
+                false => (),
+            }
+        }
+    }
+
+    /// the width of the terminal the line is drawn in, then `COLUMNS` (shells don't export it
+    /// most of the time), 80 when neither is known
+    fn terminal_width() -> usize {
+        stderr_width()
+            .or_else(|| {
+                env::var("COLUMNS")
+                    .ok()
+                    .and_then(|columns| columns.parse::<usize>().ok())
+            })
+            .unwrap_or(80)
+    }
+
+    /// asked to the terminal, so it follows when the window is resized
+    #[cfg(unix)]
+    fn stderr_width() -> Option<usize> {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, &mut size) } != 0 {
+            return None;
+        }
+        // some terminals (i.e serial consoles) don't know their size and say 0
+        Some(size.ws_col as usize).filter(|width| *width > 0)
+    }
+
+    #[cfg(not(unix))]
+    fn stderr_width() -> Option<usize> {
+        None
+    }
+
+    /// the line is never wider than the terminal, or it wouldn't be cleared properly
+    fn truncate(line: &str, width: usize) -> String {
+        match line.chars().count() < width {
+            true => line.to_string(),
+            false => {
+                let mut line = line
+                    .chars()
+                    .take(width.saturating_sub(4))
+                    .collect::<String>();
+                line.push_str("...");
+                line
+            }
+        }
+    }
+}
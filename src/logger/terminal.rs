@@ -0,0 +1,172 @@
+/// every line printed to the terminal while tasks run goes through here, so lines printed by
+/// tasks running in parallel never get mixed together
+pub mod console {
+    use std::env;
+    use std::io::{self, IsTerminal, Write};
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::Mutex;
+
+    use crate::logger::writer::LogStatus;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Stream {
+        Stdout,
+        Stderr,
+    }
+
+    impl Stream {
+        pub fn is_terminal(&self) -> bool {
+            match self {
+                Stream::Stdout => io::stdout().is_terminal(),
+                Stream::Stderr => io::stderr().is_terminal(),
+            }
+        }
+    }
+
+    /// when the output is colored, set with `--color`
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum ColorChoice {
+        /// colored when printing to a terminal and `NO_COLOR` isn't set
+        #[default]
+        Auto,
+        Always,
+        Never,
+    }
+
+    impl ColorChoice {
+        fn id(&self) -> u8 {
+            match self {
+                ColorChoice::Auto => 0,
+                ColorChoice::Always => 1,
+                ColorChoice::Never => 2,
+            }
+        }
+    }
+
+    static COLOR_CHOICE: AtomicU8 = AtomicU8::new(0);
+
+    pub fn set_color_choice(choice: ColorChoice) {
+        COLOR_CHOICE.store(choice.id(), Ordering::SeqCst)
+    }
+
+    /// if the lines written to the stream are colored
+    pub fn colors_enabled(stream: Stream) -> bool {
+        match COLOR_CHOICE.load(Ordering::SeqCst) {
+            1 => true,
+            2 => false,
+            // see https://no-color.org, an empty `NO_COLOR` doesn't count
+            _ => env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && stream.is_terminal(),
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Color {
+        Red,
+        Green,
+        Yellow,
+        Blue,
+        Magenta,
+        Cyan,
+        Dim,
+    }
+
+    impl Color {
+        fn code(&self) -> &'static str {
+            match self {
+                Color::Red => "31",
+                Color::Green => "32",
+                Color::Yellow => "33",
+                Color::Blue => "34",
+                Color::Magenta => "35",
+                Color::Cyan => "36",
+                Color::Dim => "2",
+            }
+        }
+    }
+
+    /// the text in the color, as it is when the stream isn't colored
+    pub fn paint(stream: Stream, text: &str, color: Color) -> String {
+        match colors_enabled(stream) {
+            true => format!("\x1b[{}m{text}\x1b[0m", color.code()),
+            false => text.to_string(),
+        }
+    }
+
+    /// the label of the status (i.e `ERROR`) in its color
+    pub fn paint_status(stream: Stream, status: &LogStatus) -> String {
+        let color = match status {
+            LogStatus::Error => Color::Red,
+            LogStatus::Warning => Color::Yellow,
+            LogStatus::Info => Color::Green,
+            LogStatus::ForcedAction => Color::Magenta,
+            LogStatus::Debug => Color::Blue,
+            LogStatus::Trace => Color::Dim,
+        };
+        let label: String = status.clone().into();
+        paint(stream, &label, color)
+    }
+
+    /// the line kept at the bottom of the terminal (on stderr), i.e the progress of the run,
+    /// it is cleared before any other line is written and drawn again after it
+    static STATUS_LINE: Mutex<Option<String>> = Mutex::new(None);
+
+    /// replaces the status line, `None` removes it, only used when stderr is a terminal
+    pub fn set_status_line(line: Option<String>) {
+        let mut status = STATUS_LINE.lock().unwrap_or_else(|e| e.into_inner());
+        let mut err = io::stderr().lock();
+        let _ = match &line {
+            Some(line) => write!(err, "\r\x1b[2K{line}"),
+            None if status.is_some() => write!(err, "\r\x1b[2K"),
+            None => Ok(()),
+        };
+        let _ = err.flush();
+        *status = line;
+    }
+
+    /// writes with the status line cleared, the status line lock is held so the line
+    /// can't be drawn in the middle of it
+    fn write_under_status(write: impl FnOnce()) {
+        let status = STATUS_LINE.lock().unwrap_or_else(|e| e.into_inner());
+        if status.is_some() {
+            let _ = write!(io::stderr().lock(), "\r\x1b[2K");
+        }
+
+        write();
+
+        if let Some(line) = status.as_ref() {
+            let _ = io::stdout().lock().flush();
+            let mut err = io::stderr().lock();
+            let _ = write!(err, "{line}");
+            let _ = err.flush();
+        }
+    }
+
+    /// writes a full line to the stream, the stream stays locked until the line is written
+    pub fn write_line(stream: Stream, line: &str) {
+        write_under_status(|| {
+            let _ = match stream {
+                Stream::Stdout => writeln!(io::stdout().lock(), "{line}"),
+                Stream::Stderr => writeln!(io::stderr().lock(), "{line}"),
+            };
+        })
+    }
+
+    /// writes the prefix followed by the bytes as they are, even if they aren't valid utf8
+    pub fn write_raw_line(stream: Stream, prefix: &str, bytes: &[u8]) {
+        fn write(mut out: impl Write, prefix: &str, bytes: &[u8]) -> io::Result<()> {
+            write!(out, "{prefix} ")?;
+            out.write_all(bytes)?;
+            if !bytes.ends_with(b"\n") {
+                writeln!(out)?;
+            }
+            out.flush()
+        }
+
+        write_under_status(|| {
+            let _ = match stream {
+                Stream::Stdout => write(io::stdout().lock(), prefix, bytes),
+                Stream::Stderr => write(io::stderr().lock(), prefix, bytes),
+            };
+        })
+    }
+}
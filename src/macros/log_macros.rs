@@ -12,6 +12,29 @@ macro_rules! logformat {
     };
 }
 
+/// prints a line formated with `logformat!` if the status is at or above the log level
+/// (see `-q`, `-v` and `TMPLT_LOG`), `INFO` lines go to stdout and the others to stderr,
+/// the status is colored (see `--color`)
+///
+/// # Example
+/// ```
+/// logprint!(LogStatus::Debug, "found {} tasks", tasks.len());
+/// ```
+#[macro_export]
+macro_rules! logprint {
+    ($status:expr, $($arg:tt)*) => {{
+        let status: $crate::logger::writer::LogStatus = $status;
+        if $crate::logger::writer::log_enabled(&status) {
+            let stream = match status {
+                $crate::logger::writer::LogStatus::Info => $crate::logger::console::Stream::Stdout,
+                _ => $crate::logger::console::Stream::Stderr,
+            };
+            let label = $crate::logger::console::paint_status(stream, &status);
+            $crate::logger::console::write_line(stream, &$crate::logformat!(format!($($arg)*), label))
+        }
+    }};
+}
+
 /// this function is a helper function for returning the current date
 pub fn _now() -> String {
     let date = Local::now();
@@ -1,21 +1,34 @@
+#![allow(clippy::module_inception)]
+
 use chrono::Local;
-use std::env;
+use std::fs;
 use std::sync::{Arc, Mutex};
-use std::{fs, path::Path};
 
 use crate::generator::generate;
-use crate::{arg_parser::args::get_args, core::*, logger::writer::LogWriter};
+use crate::{
+    arg_parser::args::get_args,
+    core::*,
+    logger::console::set_color_choice,
+    logger::writer::{set_log_level, watch, LogWriter},
+};
 
 use arg_parser::args::*;
-use logger::writer::LogStatus;
+use logger::writer::{LogStatus, RunEnd};
 
 pub mod arg_parser;
+pub mod check;
+pub mod config;
 pub mod core;
+pub mod etc;
+pub mod events;
+pub mod format;
 pub mod generator;
 pub mod help;
 pub mod logger;
 pub mod macros;
+pub mod report;
 pub mod tasks;
+pub mod templates;
 
 fn main() {
     // directory generator
@@ -29,87 +42,118 @@ fn main() {
     // - better error messages and a logger -> done
     // - thats all what ya ass wanting me to be dead
 
-    let exe_dir = env::current_exe().unwrap();
-    let parent = exe_dir.parent().unwrap();
-
-    if !Path::new(format!("{}\\..\\logs\\", parent.display()).as_str()).exists() {
-        match fs::create_dir(format!("{}\\..\\logs\\", parent.display()).as_str()) {
-            Ok(_) => (),
-            Err(e) => return eprintln!("{}", e),
-        }
-    };
-
     let args = match get_args() {
-        Ok(option) => option,
+        Ok(option) => {
+            set_log_level(option.get_log_level());
+            set_color_choice(*option.get_color_flag());
+            option
+        }
         Err(e) => {
             if e.no_args() {
-                return help::handle_help_command(parent);
+                return help::handle_help_command(None);
             }
 
-            let error: String = LogStatus::Error.into();
-            return println!("{}{e}", logformat!("", error));
+            // a usage error, like the exit code of check and fmt when a template can't be found
+            logprint!(LogStatus::Error, "{e}");
+            std::process::exit(2);
         }
     };
 
+    if *args.get_version_flag() {
+        return println!("tmplt {}", env!("CARGO_PKG_VERSION"));
+    }
+
     match args.get_command() {
         CommandLineArgs::New => {
             let f = match args.get_file() {
                 Some(f) => {
-                    let info: String = LogStatus::Info.into();
-                    println!("{}initializing a new .tmplt file", logformat!("", info));
-                    fs::copy(format!("{}\\..\\etc\\default.tmplt", parent.display()), f)
+                    logprint!(LogStatus::Info, "initializing a new .tmplt file");
+                    fs::write(f, etc::DEFAULT_TEMPLATE.read())
                 }
                 None => {
-                    let warn: String = LogStatus::Warning.into();
-                    println!("{}", logformat!("if you would like to initialize a new .tmplt file with any other name dont forget to include the ext as a seconde argument", warn));
-                    println!(
-                        "{}",
-                        logformat!("example: tmplt init first_look.tmplt", warn)
-                    );
-
-                    fs::copy(
-                        format!("{}\\..\\etc\\default.tmplt", parent.display()),
-                        "new.tmplt",
-                    )
+                    logprint!(LogStatus::Warning, "if you would like to initialize a new .tmplt file with any other name dont forget to include the ext as a seconde argument");
+                    logprint!(LogStatus::Warning, "example: tmplt init first_look.tmplt");
+
+                    fs::write("new.tmplt", etc::DEFAULT_TEMPLATE.read())
                 }
             };
 
             match f {
-                Ok(_) => {
-                    let status: String = LogStatus::Info.into();
-                    println!("{}", logformat!("initialized new template (tmplt) file, for more info consider reading the README.md file in the main repository", status))
-                }
-                Err(e) => {
-                    let error: String = LogStatus::Error.into();
-                    eprintln!("{}{e}", logformat!("", error))
-                }
+                Ok(_) => logprint!(LogStatus::Info, "initialized new template (tmplt) file, for more info consider reading the README.md file in the main repository"),
+                Err(e) => logprint!(LogStatus::Error, "{e}"),
             }
         }
         CommandLineArgs::Generate => {
-            let op_logger = if *args.get_save_logs_flag() {
+            tasks::install_interrupt_handler();
+
+            let op_logger = if args.is_logging_enabled() {
                 let now = Local::now();
 
-                Some(LogWriter::initialize_logger(format!(
-                    "{}",
-                    now.format("%H:%M:%S")
-                )))
+                Some(LogWriter::initialize_logger(
+                    format!("{}", now.format("%H:%M:%S")),
+                    args.get_log_options(),
+                ))
             } else {
                 None
             };
 
             let logger = op_logger.map(|logger| Arc::new(Mutex::new(logger)));
+            if let Some(logger) = &logger {
+                watch(logger.clone())
+            }
 
-            generate(args.clone(), logger.clone());
+            let end = generate(args.clone(), logger.clone());
+            let failed = matches!(end, RunEnd::Failed | RunEnd::Aborted);
 
             if let Some(logs) = logger {
-                let logs = logs.lock().unwrap();
+                let mut logs = logs.lock().unwrap();
+
+                logs.finish(end)
+            }
 
-                logs.write_to_file()
+            if tasks::is_cancelled() {
+                std::process::exit(130);
+            }
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        CommandLineArgs::Help => help::handle_help_command(args.get_help_topic()),
+        CommandLineArgs::List => templates::handle_list_command(&args),
+        CommandLineArgs::Info => {
+            if let Err(e) = templates::handle_info_command(&args) {
+                logprint!(LogStatus::Error, "{e}");
+                std::process::exit(1);
+            }
+        }
+        CommandLineArgs::Check => match check::handle_check_command(&args) {
+            Ok(true) => (),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                logprint!(LogStatus::Error, "{e}");
+                std::process::exit(2);
+            }
+        },
+        CommandLineArgs::Fmt => match format::handle_fmt_command(&args) {
+            Ok(true) => (),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                logprint!(LogStatus::Error, "{e}");
+                std::process::exit(2);
+            }
+        },
+        CommandLineArgs::Config => {
+            if let Err(e) = config::handle_config_command(&args) {
+                logprint!(LogStatus::Error, "{e}");
+                std::process::exit(1);
             }
         }
-        CommandLineArgs::Help => help::handle_help_command(parent),
         CommandLineArgs::UnknownArg => {
-            eprintln!("unknown command, use tmplt help to get more info")
+            logprint!(
+                LogStatus::Error,
+                "unknown command, use tmplt help to get more info"
+            );
+            std::process::exit(2);
         }
     }
 }
@@ -0,0 +1,222 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+use crate::logger::writer::LogStatus;
+use crate::logprint;
+use crate::tasks::TaskOutcome;
+
+/// the version of the JSON report, bumped when a field changes meaning or gets removed
+pub const REPORT_VERSION: u32 = 1;
+
+/// how many of the slowest tasks are shown in the summary
+const SLOWEST_SHOWN: usize = 5;
+
+fn count(outcomes: &[TaskOutcome], status: &str) -> usize {
+    outcomes
+        .iter()
+        .filter(|outcome| outcome.status.name() == status)
+        .count()
+}
+
+/// prints how many tasks succeeded, failed, got skipped or cancelled and the slowest ones
+pub fn print_summary(outcomes: &[TaskOutcome], duration: Duration) {
+    for (status, line) in summary_lines(outcomes, duration) {
+        logprint!(status, "{line}")
+    }
+}
+
+/// the lines of the summary and their status, they are also written to the log file
+pub fn summary_lines(outcomes: &[TaskOutcome], duration: Duration) -> Vec<(LogStatus, String)> {
+    let succeeded = count(outcomes, "succeeded");
+    let status = match succeeded == outcomes.len() {
+        true => LogStatus::Info,
+        false => LogStatus::Warning,
+    };
+
+    let mut lines = vec![(
+        status,
+        format!(
+            "summary: {succeeded} succeeded, {} failed, {} skipped, {} cancelled ({} tasks in {:.2}s)",
+            count(outcomes, "failed"),
+            count(outcomes, "skipped"),
+            count(outcomes, "cancelled"),
+            outcomes.len(),
+            duration.as_secs_f64()
+        ),
+    )];
+
+    let mut slowest = outcomes
+        .iter()
+        .filter(|outcome| !outcome.duration.is_zero())
+        .collect::<Vec<&TaskOutcome>>();
+    slowest.sort_by_key(|outcome| Reverse(outcome.duration));
+
+    if slowest.is_empty() {
+        return lines;
+    }
+
+    lines.push((LogStatus::Info, "slowest tasks:".to_string()));
+    for outcome in slowest.into_iter().take(SLOWEST_SHOWN) {
+        let line = match outcome.line {
+            Some(line) => format!("line {line}"),
+            None => "-".to_string(),
+        };
+        lines.push((
+            LogStatus::Info,
+            format!(
+                "  {:>8.2}s  task {:<4} {:<10} {:<9}  {}",
+                outcome.duration.as_secs_f64(),
+                outcome.id,
+                line,
+                outcome.status.name(),
+                outcome.label
+            ),
+        ));
+    }
+    lines
+}
+
+/// writes the outcome of the run as a JSON document, see the README for the fields
+pub fn write_report(path: &str, outcomes: &[TaskOutcome], duration: Duration) -> io::Result<()> {
+    fs::write(path, report_json(outcomes, duration))
+}
+
+/// the JSON document of the report
+pub fn report_json(outcomes: &[TaskOutcome], duration: Duration) -> String {
+    let tasks = match outcomes.is_empty() {
+        true => "[]".to_string(),
+        false => {
+            let tasks = outcomes.iter().map(task_json).collect::<Vec<String>>();
+            format!("[\n    {}\n  ]", tasks.join(",\n    "))
+        }
+    };
+
+    format!(
+        "{{\n  \"version\": {REPORT_VERSION},\n  {},\n  \"tasks\": {tasks}\n}}\n",
+        summary_fields(outcomes, duration).join(",\n  "),
+    )
+}
+
+/// the `"key": value` pairs of the counts of the run, shared by the report and the `run_finished` event
+pub fn summary_fields(outcomes: &[TaskOutcome], duration: Duration) -> Vec<String> {
+    vec![
+        format!("\"duration_ms\": {}", duration.as_millis()),
+        format!("\"succeeded\": {}", count(outcomes, "succeeded")),
+        format!("\"failed\": {}", count(outcomes, "failed")),
+        format!("\"skipped\": {}", count(outcomes, "skipped")),
+        format!("\"cancelled\": {}", count(outcomes, "cancelled")),
+    ]
+}
+
+/// the JSON object of a task in the report
+pub fn task_json(outcome: &TaskOutcome) -> String {
+    format!("{{{}}}", task_fields(outcome))
+}
+
+/// the `"key": value` pairs of a task, shared by the report and the `task_finished` event
+pub fn task_fields(outcome: &TaskOutcome) -> String {
+    format!(
+        "\"id\": {}, \"kind\": {}, \"label\": {}, \"line\": {}, \"line_end\": {}, \"defered\": {}, \"status\": {}, \"exit_code\": {}, \"duration_ms\": {}, \"stdout\": {}, \"stderr\": {}",
+        outcome.id,
+        json_string(outcome.kind),
+        json_string(&outcome.label),
+        json_option(outcome.line),
+        json_option(outcome.line_end),
+        outcome.defered,
+        json_string(outcome.status.name()),
+        json_option(outcome.exit_code),
+        outcome.duration.as_millis(),
+        json_string(&outcome.stdout),
+        json_string(&outcome.stderr),
+    )
+}
+
+pub fn json_option<T: ToString>(value: Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "null".to_string(),
+    }
+}
+
+/// quotes and escapes the text so it can be put in a JSON document
+pub fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::tasks::TaskStatus;
+
+    #[test]
+    fn json_strings_are_escaped() {
+        assert_eq!(json_string("go build"), r#""go build""#);
+        assert_eq!(json_string(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(json_string(r"C:\app"), r#""C:\\app""#);
+        assert_eq!(json_string("a\nb\r\tc"), r#""a\nb\r\tc""#);
+        assert_eq!(json_string("\u{1b}[31m"), r#""\u001b[31m""#);
+        assert_eq!(json_string("é ✓"), r#""é ✓""#);
+    }
+
+    #[test]
+    fn the_report_has_the_counts_and_every_task() {
+        let mut created =
+            TaskOutcome::new(0, "create directory a".to_string(), TaskStatus::Succeeded);
+        created.kind = "fs_action";
+        created.line = Some(2);
+        created.line_end = Some(2);
+        let mut build = TaskOutcome::new(1, "go build".to_string(), TaskStatus::Failed);
+        build.line = Some(5);
+        build.line_end = Some(6);
+        build.exit_code = Some(1);
+        build.duration = Duration::from_millis(25);
+        build.stderr = "no Go files in /app".to_string();
+        let skipped = TaskOutcome::new(2, "go test".to_string(), TaskStatus::Skipped);
+
+        let report = report_json(&[created, build, skipped], Duration::from_millis(357));
+        assert_eq!(
+            report,
+            r#"{
+  "version": 1,
+  "duration_ms": 357,
+  "succeeded": 1,
+  "failed": 1,
+  "skipped": 1,
+  "cancelled": 0,
+  "tasks": [
+    {"id": 0, "kind": "fs_action", "label": "create directory a", "line": 2, "line_end": 2, "defered": false, "status": "succeeded", "exit_code": null, "duration_ms": 0, "stdout": "", "stderr": ""},
+    {"id": 1, "kind": "command", "label": "go build", "line": 5, "line_end": 6, "defered": false, "status": "failed", "exit_code": 1, "duration_ms": 25, "stdout": "", "stderr": "no Go files in /app"},
+    {"id": 2, "kind": "command", "label": "go test", "line": null, "line_end": null, "defered": false, "status": "skipped", "exit_code": null, "duration_ms": 0, "stdout": "", "stderr": ""}
+  ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn an_empty_run_has_no_tasks() {
+        let report = report_json(&[], Duration::ZERO);
+        assert!(
+            report.ends_with("\"cancelled\": 0,\n  \"tasks\": []\n}\n"),
+            "{report}"
+        );
+    }
+}
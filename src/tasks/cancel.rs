@@ -0,0 +1,123 @@
+pub mod cancellation {
+    use std::process::Child;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::logger::writer::{finish_active_logger, LogStatus, RunEnd};
+    use crate::logprint;
+
+    /// set once the run is cancelled (i.e by ctrl-c), tasks that didn't start yet won't run
+    static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+    /// the pids of the child processes that are currently running
+    static RUNNING_CHILDREN: Mutex<Vec<u32>> = Mutex::new(vec![]);
+
+    /// how long a child process has to exit after being asked to before it gets killed
+    const GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+    pub fn is_cancelled() -> bool {
+        CANCELLED.load(Ordering::SeqCst)
+    }
+
+    /// cancels the run, the running child processes are asked to stop and the tasks that didn't start won't run
+    pub fn cancel_all() {
+        CANCELLED.store(true, Ordering::SeqCst);
+
+        for pid in RUNNING_CHILDREN.lock().unwrap().iter() {
+            signal_tree(*pid, false)
+        }
+    }
+
+    /// installs the ctrl-c handler, the first ctrl-c cancels the run and the second one exits right away
+    pub fn install_interrupt_handler() {
+        let result = ctrlc::set_handler(|| {
+            if is_cancelled() {
+                finish_active_logger(RunEnd::Interrupted);
+                std::process::exit(130);
+            }
+
+            logprint!(
+                LogStatus::Warning,
+                "cancelling, waiting for the running tasks to stop (press ctrl-c again to exit now)"
+            );
+            cancel_all()
+        });
+
+        if let Err(e) = result {
+            logprint!(LogStatus::Warning, "unable to handle ctrl-c: {e}");
+        }
+    }
+
+    pub fn register_child(child: &Child) {
+        RUNNING_CHILDREN.lock().unwrap().push(child.id());
+
+        // the run could have been cancelled right before the child got registered
+        if is_cancelled() {
+            signal_tree(child.id(), false)
+        }
+    }
+
+    pub fn unregister_child(child: &Child) {
+        RUNNING_CHILDREN
+            .lock()
+            .unwrap()
+            .retain(|pid| *pid != child.id());
+    }
+
+    /// stops the child and every process it started, it is asked to stop first and killed if
+    /// it is still running after the grace period
+    pub fn terminate_child(child: &mut Child) {
+        signal_tree(child.id(), false);
+
+        let start = Instant::now();
+        while start.elapsed() < GRACE_PERIOD {
+            if let Ok(Some(_)) = child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        signal_tree(child.id(), true);
+        let _ = child.wait();
+    }
+
+    /// sleeps for the given duration, returns early (with `false`) if the run gets cancelled
+    pub fn sleep_unless_cancelled(duration: Duration) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < duration {
+            if is_cancelled() {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(50).min(duration - start.elapsed()));
+        }
+        !is_cancelled()
+    }
+
+    /// the child processes are started in their own process group (except the interactive ones),
+    /// so the whole group gets the signal
+    #[cfg(unix)]
+    fn signal_tree(pid: u32, force: bool) {
+        let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+
+        // a negative pid sends the signal to every process of the group, an interactive command
+        // isn't in its own group so it gets the signal alone
+        unsafe {
+            if libc::kill(-(pid as libc::pid_t), signal) != 0 {
+                libc::kill(pid as libc::pid_t, signal);
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    fn signal_tree(pid: u32, force: bool) {
+        let mut taskkill = std::process::Command::new("taskkill");
+        taskkill.args(["/T", "/PID", &pid.to_string()]);
+        if force {
+            taskkill.arg("/F");
+        }
+
+        let _ = taskkill.output();
+    }
+}
@@ -1,141 +1,335 @@
 pub mod task_executor {
-    use core::fmt;
-    use std::thread::{self, JoinHandle};
+    use std::collections::VecDeque;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Mutex;
+    use std::thread;
 
-    use crate::tasks::{Task, TaskError, TaskSchedular};
-    pub type ThreadResult = JoinHandle<Option<TaskError>>;
+    use crate::logger::writer::LogStatus;
+    use crate::logprint;
+    use crate::tasks::{is_cancelled, Task, TaskOutcome, TaskSchedular, TaskStatus};
 
+    /// where the executor is in the run, it only moves forward:
+    /// `Normal` -> `Paused` -> `Defered` -> `Done`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ExecutionPhase {
+        /// giving out the batches of the tasks that aren't defered
+        Normal,
+        /// every task that isn't defered was given out, waiting for `start_defered_phase`
+        Paused,
+        /// giving out the batches of the defered tasks
+        Defered,
+        /// every task was given out
+        Done,
+    }
+
+    /// gives out the tasks in batches, first the tasks that aren't defered until there are none left,
+    /// then (once `start_defered_phase` is called) the defered ones.
+    ///
+    /// every task is taken out of its queue when it is given out, so it can't be given twice
     #[derive(Debug, Clone)]
     pub struct TasksExecutor {
-        _task_schedular: TaskSchedular,
+        normal_tasks: VecDeque<Task>,
+        defered_tasks: VecDeque<Task>,
         batch_size: usize,
-        /// the `switch` field is a toggle so that the taskExecutor will only give batchs of not defered
-        /// task and stops untill it turns on  
-        switch: bool,
-        current_iterations: usize,
-        temp_buf: Vec<Task>,
+        phase: ExecutionPhase,
 
         pub current_batch: Vec<Task>,
     }
-    #[derive(Clone)]
-    pub struct ExecutionError;
 
-    impl fmt::Debug for ExecutionError {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "faild to execute batchs , found empty batch.")
+    /// runs the tasks of a batch one after the other, returns the outcome of every task in the batch
+    pub fn run_batch(batch: Vec<Task>) -> Vec<TaskOutcome> {
+        let mut outcomes: Vec<TaskOutcome> = vec![];
+        let mut tasks = batch.into_iter();
+
+        while let Some(task) = tasks.next() {
+            // a panicking task fails alone, the tasks depending on it are told it didn't succeed
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| task.execute_task()))
+                .unwrap_or_else(|_| task.skip(TaskStatus::Failed));
+
+            let succeeded = outcome.succeeded();
+            outcomes.push(outcome);
+            if !succeeded {
+                // the rest of the batch won't run, the tasks depending on them shouldn't wait for them
+                let status = match is_cancelled() {
+                    true => TaskStatus::Cancelled,
+                    false => TaskStatus::Skipped,
+                };
+                outcomes.extend(tasks.by_ref().map(|task| task.skip(status.clone())));
+            }
         }
+        outcomes
     }
 
-    pub fn execute_batch(batch: Vec<Task>) -> Result<ThreadResult, ExecutionError> {
-        if batch.is_empty() {
-            return Err(ExecutionError);
-        }
+    /// runs the batches given by the executor on `jobs` worker threads, each worker takes the next
+    /// batch once it is done with its current one
+    ///
+    /// the batches are taken in order and a task only depends on tasks placed before it,
+    /// so a worker waiting for a dependency always waits for a batch that was already taken
+    ///
+    /// a panicking task fails alone (see `run_batch`), a worker panicking outside of a task would lose
+    /// the outcomes of the tasks it ran so the panic is passed on to the caller
+    pub fn execute_batches(executor: &mut TasksExecutor, jobs: usize) -> Vec<TaskOutcome> {
+        // once a worker is told there are no batches left, the others shouldn't ask again
+        let queue = Mutex::new(executor.fuse());
 
-        let thread = thread::spawn(move || -> Option<TaskError> {
-            for task in batch {
-                match task.execute_task() {
-                    Err(e) => return Some(e),
-                    Ok(_) => continue,
-                }
-            }
-            None
-        });
+        thread::scope(|scope| {
+            let workers = (0..jobs.max(1))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut outcomes: Vec<TaskOutcome> = vec![];
+                        loop {
+                            let batch = match queue.lock().unwrap().next() {
+                                Some(batch) => batch,
+                                None => break,
+                            };
+                            logprint!(
+                                LogStatus::Trace,
+                                "{:?} took a batch of tasks {:?}",
+                                thread::current().id(),
+                                batch.iter().map(|t| t.id()).collect::<Vec<usize>>()
+                            );
+                            outcomes.append(&mut run_batch(batch));
+                        }
+                        outcomes
+                    })
+                })
+                .collect::<Vec<_>>();
 
-        Ok(thread)
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|panic| panic::resume_unwind(panic))
+                })
+                .collect::<Vec<TaskOutcome>>()
+        })
     }
+
     impl TasksExecutor {
         pub fn new(task_schedular: TaskSchedular, batch_size: usize) -> Self {
-            let current_batch: Vec<Task> = Vec::with_capacity(batch_size);
+            let (normal_tasks, defered_tasks) = task_schedular.into_phases();
 
             TasksExecutor {
-                _task_schedular: task_schedular,
-                current_batch,
-                batch_size,
-                current_iterations: 0,
-                switch: false,
-                temp_buf: vec![],
+                normal_tasks: normal_tasks.into(),
+                defered_tasks: defered_tasks.into(),
+                batch_size: batch_size.max(1),
+                phase: ExecutionPhase::Normal,
+                current_batch: vec![],
             }
         }
 
-        pub fn toggle_switch(&mut self) -> &mut Self {
-            self.switch = !self.switch;
+        /// the tasks that weren't given out yet, in the order they will be
+        pub fn planned_tasks(&self) -> Vec<&Task> {
+            self.normal_tasks
+                .iter()
+                .chain(self.defered_tasks.iter())
+                .collect()
+        }
+
+        pub fn phase(&self) -> ExecutionPhase {
+            self.phase
+        }
+
+        /// starts giving out the defered tasks, only once every task that isn't defered was given out
+        pub fn start_defered_phase(&mut self) -> &mut Self {
+            if self.phase == ExecutionPhase::Normal && self.normal_tasks.is_empty() {
+                self.phase = ExecutionPhase::Paused;
+            }
+
+            if self.phase == ExecutionPhase::Paused {
+                self.phase = ExecutionPhase::Defered;
+            }
 
             self
         }
+
+        fn take_batch(queue: &mut VecDeque<Task>, batch_size: usize) -> Vec<Task> {
+            let size = batch_size.min(queue.len());
+            queue.drain(..size).collect()
+        }
     }
 
     impl Iterator for &mut TasksExecutor {
         type Item = Vec<Task>;
 
+        /// never gives an empty batch, `None` ends the current phase
         fn next(&mut self) -> Option<Self::Item> {
-            let mut next_batch: Vec<Task> = self
-                ._task_schedular
-                .clone()
-                .skip(self.current_iterations * self.batch_size)
-                .take(self.batch_size)
-                .take_while(|v| !v.defered || self.switch)
-                .collect();
-
-            if next_batch.is_empty() {
-                // we decrease it by one so if the switch is on in the next iteration it will return the
-                // defered tasks
-                self.current_iterations -= 1;
-                return None;
-            }
+            let next_batch = match self.phase {
+                ExecutionPhase::Normal if self.normal_tasks.is_empty() => {
+                    self.phase = ExecutionPhase::Paused;
+                    return None;
+                }
+                ExecutionPhase::Normal => {
+                    TasksExecutor::take_batch(&mut self.normal_tasks, self.batch_size)
+                }
+                ExecutionPhase::Defered if self.defered_tasks.is_empty() => {
+                    self.phase = ExecutionPhase::Done;
+                    return None;
+                }
+                ExecutionPhase::Defered => {
+                    TasksExecutor::take_batch(&mut self.defered_tasks, self.batch_size)
+                }
+                ExecutionPhase::Paused | ExecutionPhase::Done => return None,
+            };
 
-            if self.current_iterations * self.batch_size >= self._task_schedular.len() {
-                return None;
-            }
+            self.current_batch = next_batch.clone();
+            Some(next_batch)
+        }
+    }
+}
 
-            if self.batch_size >= self._task_schedular.len() {
-                self.current_iterations += 1;
-                let mut next_batch: Vec<Task> = self
-                    ._task_schedular
-                    .clone()
-                    .take_while(|v| !v.defered || self.switch)
-                    .collect();
-                // a filter if the batch size is bigger than tasks and to filter only defered tasks
-                if self.switch {
-                    let mut filtered = next_batch
-                        .iter()
-                        .filter(|v| v.defered)
-                        .map(|v| v.to_owned())
-                        .collect::<Vec<Task>>();
-                    next_batch.clear();
-                    next_batch.append(&mut filtered);
-                }
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
 
-                let leftover_batch: Vec<Task> =
-                    self._task_schedular.clone().filter(|t| t.defered).collect();
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
 
-                self.temp_buf = leftover_batch;
+    use super::task_executor::*;
+    use crate::core::ExecutableCommand;
+    use crate::tasks::{Task, TaskContext, TaskSchedular};
 
-                if !self.temp_buf.is_empty() && self.switch {
-                    let temp = self.temp_buf.clone();
-                    self.temp_buf.clear();
-                    return Some(temp);
-                }
+    /// a command that records the id of its task when it runs
+    #[derive(Clone)]
+    struct Record {
+        id: usize,
+        ran: Arc<Mutex<Vec<usize>>>,
+    }
 
-                if next_batch.is_empty() {
-                    return None;
-                }
+    impl ExecutableCommand<String> for Record {
+        fn _execute(&self, _context: &TaskContext) -> Result<String, String> {
+            self.ran.lock().unwrap().push(self.id);
+            Ok(format!("ran task {}", self.id))
+        }
 
-                return Some(next_batch);
-            }
+        fn describe(&self) -> String {
+            format!("record {}", self.id)
+        }
+    }
 
-            if self.switch {
-                let mut filtered = next_batch
-                    .iter()
-                    .filter(|v| v.defered)
-                    .map(|v| v.to_owned())
-                    .collect::<Vec<Task>>();
-                next_batch.clear();
-                next_batch.append(&mut filtered);
+    /// random tasks, some defered and some depending on a task declared before them
+    fn random_tasks(rng: &mut StdRng, ran: &Arc<Mutex<Vec<usize>>>) -> Vec<Task> {
+        let len = rng.gen_range(0..60);
+        let defered_ratio = rng.gen_range(0.0..=1.0);
+        let mut tasks: Vec<Task> = vec![];
+
+        for id in 0..len {
+            let cmd = Record {
+                id,
+                ran: ran.clone(),
+            };
+            let mut task = Task::new(cmd, rng.gen_bool(defered_ratio), id, None);
+            if id > 0 && rng.gen_bool(0.3) {
+                let dep = tasks[rng.gen_range(0..id)].clone();
+                task.depends_on(&dep);
             }
+            tasks.push(task)
+        }
+        tasks
+    }
 
-            self.current_iterations += 1;
-            self.current_batch = next_batch.clone();
-            Some(next_batch)
+    fn drain_phase(executor: &mut TasksExecutor, batch_size: usize, seed: u64) -> Vec<Task> {
+        let mut given: Vec<Task> = vec![];
+        for batch in &mut *executor {
+            assert!(!batch.is_empty(), "empty batch (seed {seed})");
+            assert!(batch.len() <= batch_size, "batch too big (seed {seed})");
+            given.extend(batch)
+        }
+        // the phase is over, asking again gives nothing
+        assert!((&mut *executor).next().is_none(), "seed {seed}");
+        given
+    }
+
+    #[test]
+    fn every_task_is_given_once_in_its_phase() {
+        for seed in 0..500 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let tasks = random_tasks(&mut rng, &Arc::default());
+            let batch_size = rng.gen_range(1..=tasks.len() + 5);
+            let expected_normal = tasks
+                .iter()
+                .filter(|t| !t.defered)
+                .map(|t| t.id())
+                .collect::<Vec<usize>>();
+            let expected_defered = tasks
+                .iter()
+                .filter(|t| t.defered)
+                .map(|t| t.id())
+                .collect::<Vec<usize>>();
+
+            let mut executor = TasksExecutor::new(TaskSchedular::new(tasks), batch_size);
+
+            let normal = drain_phase(&mut executor, batch_size, seed);
+            assert_eq!(executor.phase(), ExecutionPhase::Paused, "seed {seed}");
+            let normal = normal.iter().map(|t| t.id()).collect::<Vec<usize>>();
+            assert_eq!(normal, expected_normal, "seed {seed}");
+
+            executor.start_defered_phase();
+            let defered = drain_phase(&mut executor, batch_size, seed);
+            assert_eq!(executor.phase(), ExecutionPhase::Done, "seed {seed}");
+            let defered = defered.iter().map(|t| t.id()).collect::<Vec<usize>>();
+            assert_eq!(defered, expected_defered, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn defered_phase_waits_for_the_normal_tasks() {
+        let ran: Arc<Mutex<Vec<usize>>> = Arc::default();
+        let tasks = (0..4)
+            .map(|id| {
+                let cmd = Record {
+                    id,
+                    ran: ran.clone(),
+                };
+                Task::new(cmd, id % 2 == 0, id, None)
+            })
+            .collect::<Vec<Task>>();
+        let mut executor = TasksExecutor::new(TaskSchedular::new(tasks), 1);
+
+        executor.start_defered_phase();
+        assert_eq!(executor.phase(), ExecutionPhase::Normal);
+        let first = (&mut executor).next().unwrap();
+        assert_eq!(first[0].id(), 1);
+    }
+
+    #[test]
+    fn every_task_runs_once_in_its_phase() {
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let ran: Arc<Mutex<Vec<usize>>> = Arc::default();
+            let tasks = random_tasks(&mut rng, &ran);
+            let batch_size = rng.gen_range(1..=tasks.len() + 5);
+            let jobs = rng.gen_range(1..=8);
+            let defered = tasks
+                .iter()
+                .filter(|t| t.defered)
+                .map(|t| t.id())
+                .collect::<Vec<usize>>();
+            let len = tasks.len();
+
+            let mut executor = TasksExecutor::new(TaskSchedular::new(tasks), batch_size);
+            let outcomes = execute_batches(&mut executor, jobs);
+            assert!(outcomes.iter().all(|o| o.succeeded()), "seed {seed}");
+            let normal_ran = ran.lock().unwrap().len();
+
+            let outcomes = execute_batches(executor.start_defered_phase(), jobs);
+            assert!(outcomes.iter().all(|o| o.succeeded()), "seed {seed}");
+
+            let ran = ran.lock().unwrap();
+            assert_eq!(normal_ran, len - defered.len(), "seed {seed}");
+            assert!(
+                ran[..normal_ran].iter().all(|id| !defered.contains(id)),
+                "seed {seed}"
+            );
+            assert!(
+                ran[normal_ran..].iter().all(|id| defered.contains(id)),
+                "seed {seed}"
+            );
+
+            let mut sorted = ran.clone();
+            sorted.sort();
+            assert_eq!(sorted, (0..len).collect::<Vec<usize>>(), "seed {seed}");
         }
     }
 }
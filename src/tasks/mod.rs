@@ -2,10 +2,14 @@
 // and it is giving to a TasksExecutor which execute tasks
 // by their ordered depending if its defered or not
 // the order of what to execute first is determined by the TaskSchedular struct
+mod cancel;
 mod executor;
+mod outcome;
 mod schedular;
 mod task;
 
+pub use cancel::cancellation::*;
 pub use executor::task_executor::*;
+pub use outcome::outcome::*;
 pub use schedular::task_schedular::*;
 pub use task::task::*;
@@ -0,0 +1,120 @@
+pub mod outcome {
+    use std::fmt::{self, Display};
+    use std::time::Duration;
+
+    /// how many of the last lines of a command output are kept in its outcome
+    const EXCERPT_LINES: usize = 20;
+
+    /// the maximum length (in bytes) of an output excerpt
+    const EXCERPT_LEN: usize = 4096;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum TaskStatus {
+        Succeeded,
+        Failed,
+        /// the script was stopped after running for its `TIMEOUT`, it counts as failed
+        TimedOut(Duration),
+        /// the task didn't run because the task (its id) it depends on didn't succeed
+        DependencyFailed(usize),
+        /// the task didn't run because a task before it in the same batch failed
+        Skipped,
+        /// the run got cancelled before or while the task was running
+        Cancelled,
+    }
+
+    impl TaskStatus {
+        /// the name used in the summary and the report, a task that didn't run is `skipped`
+        pub fn name(&self) -> &'static str {
+            match self {
+                TaskStatus::Succeeded => "succeeded",
+                TaskStatus::Failed | TaskStatus::TimedOut(_) => "failed",
+                TaskStatus::DependencyFailed(_) | TaskStatus::Skipped => "skipped",
+                TaskStatus::Cancelled => "cancelled",
+            }
+        }
+    }
+
+    /// what happened to a task, given back by every task whether it ran or not
+    #[derive(Clone, Debug)]
+    pub struct TaskOutcome {
+        pub id: usize,
+        /// what the task does, `command` or `fs_action`
+        pub kind: &'static str,
+        pub label: String,
+        /// the line of the template the task comes from
+        pub line: Option<usize>,
+        /// the last line of the task in the template
+        pub line_end: Option<usize>,
+        pub defered: bool,
+        pub status: TaskStatus,
+        /// the exit code of the script, `None` for the other tasks or if the script got killed
+        pub exit_code: Option<i32>,
+        /// how long the task ran, without the time spent waiting for its dependencies
+        pub duration: Duration,
+        /// the last lines of the output of the script
+        pub stdout: String,
+        pub stderr: String,
+    }
+
+    impl TaskOutcome {
+        pub fn new(id: usize, label: String, status: TaskStatus) -> Self {
+            TaskOutcome {
+                id,
+                kind: "command",
+                label,
+                line: None,
+                line_end: None,
+                defered: false,
+                status,
+                exit_code: None,
+                duration: Duration::ZERO,
+                stdout: String::new(),
+                stderr: String::new(),
+            }
+        }
+
+        pub fn succeeded(&self) -> bool {
+            self.status == TaskStatus::Succeeded
+        }
+    }
+
+    /// the last lines of an output, so a failing script can be understood without the whole log
+    pub fn excerpt(output: &str) -> String {
+        let lines = output.lines().collect::<Vec<&str>>();
+        let kept = lines[lines.len().saturating_sub(EXCERPT_LINES)..].join("\n");
+
+        let mut start = kept.len().saturating_sub(EXCERPT_LEN);
+        while !kept.is_char_boundary(start) {
+            start += 1;
+        }
+        kept[start..].to_string()
+    }
+
+    /// the line printed and logged for the task, i.e `task 3 (go build) failed with exit code 1 (line 12)`
+    impl Display for TaskOutcome {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "task {} ({}) ", self.id, self.label)?;
+            match self.status {
+                TaskStatus::Succeeded => write!(f, "succeeded")?,
+                TaskStatus::Failed => {
+                    write!(f, "failed")?;
+                    if let Some(code) = self.exit_code {
+                        write!(f, " with exit code {code}")?;
+                    }
+                }
+                TaskStatus::TimedOut(timeout) => write!(f, "timed out after {timeout:?}")?,
+                TaskStatus::DependencyFailed(dep) => {
+                    write!(f, "skipped, it depends on task {dep} which didn't succeed")?
+                }
+                TaskStatus::Skipped => {
+                    write!(f, "skipped, a task before it in the same batch failed")?
+                }
+                TaskStatus::Cancelled => write!(f, "cancelled")?,
+            }
+            match self.line {
+                Some(line) => write!(f, " (line {line})"),
+                None => Ok(()),
+            }
+        }
+    }
+}
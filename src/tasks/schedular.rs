@@ -1,49 +1,98 @@
 pub mod task_schedular {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Condvar, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
     use crate::tasks::Task;
 
+    /// every running task holds a shared slot, an interactive task takes the exclusive slot
+    /// so no other task runs (or prints) while it uses the terminal
+    static RUN_SLOT: RwLock<()> = RwLock::new(());
+
+    /// held by a task for as long as it runs
+    pub enum RunSlot {
+        Shared(RwLockReadGuard<'static, ()>),
+        Exclusive(RwLockWriteGuard<'static, ()>),
+    }
+
+    /// blocks until the task is allowed to run, an exclusive slot waits for every running task to finish
+    pub fn acquire_run_slot(exclusive: bool) -> RunSlot {
+        // a task that panicked while holding the slot doesn't matter to the others
+        match exclusive {
+            true => RunSlot::Exclusive(RUN_SLOT.write().unwrap_or_else(|e| e.into_inner())),
+            false => RunSlot::Shared(RUN_SLOT.read().unwrap_or_else(|e| e.into_inner())),
+        }
+    }
+
+    /// how much of the machine a task uses, a heavy task (i.e `npm install`) runs alongside
+    /// a limited number of other heavy tasks
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum ResourceClass {
+        #[default]
+        Light,
+        Heavy,
+    }
+
+    /// how many heavy tasks can run at the same time, set with `--heavy-jobs`
+    static HEAVY_LIMIT: AtomicUsize = AtomicUsize::new(1);
+
+    /// the number of heavy tasks running right now
+    static HEAVY_RUNNING: (Mutex<usize>, Condvar) = (Mutex::new(0), Condvar::new());
+
+    pub fn set_heavy_limit(limit: usize) {
+        HEAVY_LIMIT.store(limit.max(1), Ordering::SeqCst)
+    }
+
+    /// held by a task for as long as it runs, frees a place for the next task of the same class when dropped
+    pub struct ClassSlot(ResourceClass);
+
+    /// blocks until a task of the class is allowed to run, light tasks never wait
+    pub fn acquire_class_slot(class: ResourceClass) -> ClassSlot {
+        if class == ResourceClass::Heavy {
+            let (lock, cvar) = &HEAVY_RUNNING;
+            let mut running = lock.lock().unwrap_or_else(|e| e.into_inner());
+            while *running >= HEAVY_LIMIT.load(Ordering::SeqCst) {
+                running = cvar.wait(running).unwrap_or_else(|e| e.into_inner());
+            }
+            *running += 1;
+        }
+
+        ClassSlot(class)
+    }
+
+    impl Drop for ClassSlot {
+        fn drop(&mut self) {
+            if self.0 == ResourceClass::Heavy {
+                let (lock, cvar) = &HEAVY_RUNNING;
+                *lock.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+                cvar.notify_one();
+            }
+        }
+    }
+
+    /// orders the tasks, the not defered ones first then the defered ones, each group keeps the order
+    /// the tasks were declared in
     #[derive(Clone, Debug)]
     pub struct TaskSchedular {
         tasks: Vec<Task>,
         defered_tasks_buf: Vec<Task>,
         pub current_itereations: usize,
-
-        pub current_task: Task,
     }
 
     impl TaskSchedular {
-        pub fn new(mut tasks: Vec<Task>) -> Self {
-            let defered_buf: Vec<Task> = TaskSchedular::seperate_defered_tasks(&mut tasks);
-            let tasks: Vec<Task> = tasks.iter().filter(|v| !v.defered).cloned().collect();
-
-            if tasks.is_empty() {
-                let first_task = defered_buf[0].clone();
-                return TaskSchedular {
-                    tasks: tasks.to_owned(),
-                    defered_tasks_buf: defered_buf,
-                    current_itereations: 0,
-                    current_task: first_task,
-                };
-            }
+        pub fn new(tasks: Vec<Task>) -> Self {
+            let (defered_buf, tasks): (Vec<Task>, Vec<Task>) =
+                tasks.into_iter().partition(|task| task.defered);
 
-            let first_task = tasks[0].clone();
             TaskSchedular {
-                tasks: tasks.to_owned(),
+                tasks,
                 defered_tasks_buf: defered_buf,
                 current_itereations: 0,
-                current_task: first_task,
             }
         }
 
-        fn seperate_defered_tasks(tasks: &mut [Task]) -> Vec<Task> {
-            let mut buffer: Vec<Task> = vec![];
-
-            for task in tasks.to_owned().clone().iter() {
-                if task.defered {
-                    buffer.push(task.clone());
-                }
-            }
-
-            buffer
+        /// splits the tasks into the ones to run first and the defered ones
+        pub fn into_phases(self) -> (Vec<Task>, Vec<Task>) {
+            (self.tasks, self.defered_tasks_buf)
         }
 
         pub fn len(&self) -> usize {
@@ -59,25 +108,15 @@ pub mod task_schedular {
         type Item = Task;
 
         fn next(&mut self) -> Option<Self::Item> {
-            if self.current_itereations == 0 {
-                self.current_itereations += 1;
-                return Some(self.current_task.clone());
-            }
-
-            if self.current_itereations == (self.tasks.len() + self.defered_tasks_buf.len()) {
-                return None;
-            }
+            let task = match self.current_itereations.checked_sub(self.tasks.len()) {
+                Some(defered_idx) => self.defered_tasks_buf.get(defered_idx),
+                None => self.tasks.get(self.current_itereations),
+            };
 
-            if self.current_itereations >= self.tasks.len() {
-                let current_idx = self.current_itereations - self.tasks.len();
-                self.current_task = self.defered_tasks_buf[current_idx].clone();
+            if task.is_some() {
                 self.current_itereations += 1;
-                return Some(self.current_task.clone());
             }
-
-            self.current_task = self.tasks[self.current_itereations].clone();
-            self.current_itereations += 1;
-            Some(self.current_task.clone())
+            task.cloned()
         }
     }
 }
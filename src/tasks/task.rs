@@ -1,15 +1,19 @@
 pub mod task {
     use std::{
-        fmt::{Debug, Display},
-        sync::{Arc, Mutex},
+        fmt::Debug,
+        sync::{Arc, Condvar, Mutex},
+        time::Instant,
     };
 
     use crate::{
-        core::ExecutableCommand,
-        logformat,
-        logger::writer::{
-            LogStatus::{self, *},
-            LogWriter,
+        core::{CommandOutput, ExecutableCommand},
+        events,
+        logger::progress,
+        logger::writer::{LogStatus, LogWriter},
+        logprint,
+        tasks::{
+            acquire_class_slot, acquire_run_slot, excerpt, is_cancelled, ResourceClass,
+            TaskOutcome, TaskStatus,
         },
     };
     // a Task struct has the information about the task
@@ -19,21 +23,60 @@ pub mod task {
 
     pub type OpArcMutex<T> = Option<Arc<Mutex<T>>>;
 
+    /// what a task gives to the command it executes, i.e for prefixing and logging the command output
+    #[derive(Clone)]
+    pub struct TaskContext {
+        pub id: usize,
+        pub logger: OpArcMutex<LogWriter>,
+    }
+
     #[derive(Clone)]
     pub struct Task {
-        _task_fn: Arc<dyn Fn() -> Option<TaskError> + Sync + Send>,
+        _task_fn: Arc<dyn Fn() -> TaskOutcome + Sync + Send>,
         id: usize,
+        /// a short description of the task (i.e the command it runs), used when reporting the task
+        label: String,
+        /// the line of the template the task comes from
+        line: Option<usize>,
+        line_end: Option<usize>,
+        /// what the task does, `command` or `fs_action`
+        kind: &'static str,
+        signal: TaskSignal,
+        /// the tasks (their id and signal) that should finish successfully before this task runs
+        dependencies: Vec<(usize, TaskSignal)>,
+        /// an exclusive task runs alone, i.e an interactive command that uses the terminal
+        exclusive: bool,
+        /// heavy tasks are limited to a few running at the same time
+        class: ResourceClass,
 
         pub defered: bool,
     }
-    #[derive(Clone)]
-    pub struct TaskError {
-        id: usize,
+    /// the completion state of a task, shared with the tasks that depend on it
+    #[derive(Clone, Default)]
+    pub struct TaskSignal {
+        state: Arc<(Mutex<Option<bool>>, Condvar)>,
     }
 
-    impl Debug for TaskError {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "Task {} failed", self.id)
+    impl TaskSignal {
+        pub fn finish(&self, succeeded: bool) {
+            let (lock, cvar) = &*self.state;
+            let mut state = lock.lock().unwrap();
+            if state.is_none() {
+                *state = Some(succeeded);
+            }
+            cvar.notify_all();
+        }
+
+        /// blocks until the task finishes and returns if it succeeded
+        pub fn wait(&self) -> bool {
+            let (lock, cvar) = &*self.state;
+            let mut state = lock.lock().unwrap();
+            loop {
+                match *state {
+                    Some(succeeded) => return succeeded,
+                    None => state = cvar.wait(state).unwrap(),
+                }
+            }
         }
     }
 
@@ -51,36 +94,154 @@ pub mod task {
             mutex_logger: OpArcMutex<LogWriter>,
         ) -> Self
         where
-            O: Display,
+            O: CommandOutput,
             T: ExecutableCommand<O> + Clone + 'static + Sync + Send,
         {
+            let label = execute.describe();
+            let line = execute.line();
+            let line_end = execute.line_end();
+            let kind = execute.kind();
+            let exclusive = execute.is_interactive();
+            let class = execute.resource_class();
+            let task_label = label.clone();
             let execute_copy = execute.clone();
-            let task = move || match execute_copy._execute() {
-                Some(out) => {
-                    let status: String = LogStatus::Info.into();
-                    println!("{}", logformat!(out, status));
+            let context = TaskContext {
+                id,
+                logger: mutex_logger.clone(),
+            };
+            let task = move || {
+                let (out, status) = match execute_copy._execute(&context) {
+                    Ok(out) => (out, TaskStatus::Succeeded),
+                    Err(out) if is_cancelled() => (out, TaskStatus::Cancelled),
+                    Err(out) => match out.timed_out() {
+                        Some(timeout) => (out, TaskStatus::TimedOut(timeout)),
+                        None => (out, TaskStatus::Failed),
+                    },
+                };
+
+                if status == TaskStatus::Succeeded && !events::enabled() {
+                    logprint!(LogStatus::Info, "{out}");
                     if let Some(logger) = mutex_logger.clone() {
                         let mut log = logger.lock().unwrap();
 
-                        log.write(format!("{out}"), Info)
+                        log.write(format!("{out}"), LogStatus::Info)
                     }
-                    None
                 }
-                None => Some(TaskError { id }),
+
+                let mut outcome = TaskOutcome::new(id, task_label.clone(), status);
+                outcome.exit_code = out.exit_code();
+                outcome.stdout = excerpt(out.stdout());
+                outcome.stderr = excerpt(out.stderr());
+                outcome
             };
 
             Task {
                 _task_fn: Arc::new(task),
+                label,
+                line,
+                line_end,
+                kind,
+                signal: TaskSignal::default(),
+                dependencies: vec![],
+                exclusive,
+                class,
                 defered,
                 id,
             }
         }
 
-        pub fn execute_task(&self) -> Result<(), TaskError> {
-            match (self._task_fn)() {
-                Some(err) => Err(err),
-                None => Ok(()),
+        pub fn id(&self) -> usize {
+            self.id
+        }
+
+        pub fn label(&self) -> &str {
+            &self.label
+        }
+
+        pub fn line(&self) -> Option<usize> {
+            self.line
+        }
+
+        /// the last line of the task in the template, a script can be continued over several lines
+        pub fn line_end(&self) -> Option<usize> {
+            self.line_end
+        }
+
+        pub fn kind(&self) -> &'static str {
+            self.kind
+        }
+
+        /// the ids of the tasks this task waits for
+        pub fn dependency_ids(&self) -> Vec<usize> {
+            self.dependencies.iter().map(|(id, _)| *id).collect()
+        }
+
+        /// makes this task wait for `other` to finish successfully before running,
+        /// a task depending on a defered task is defered as well
+        pub fn depends_on(&mut self, other: &Task) {
+            if other.defered {
+                self.defered = true;
+            }
+
+            self.dependencies.push((other.id, other.signal.clone()))
+        }
+
+        /// marks the task as not executed so the tasks depending on it don't wait for it forever
+        pub fn skip(&self, status: TaskStatus) -> TaskOutcome {
+            self.signal.finish(false);
+            self.outcome(TaskOutcome::new(self.id, self.label.clone(), status))
+        }
+
+        /// fills what the task knows about itself in the outcome, every task ends here once
+        fn outcome(&self, mut outcome: TaskOutcome) -> TaskOutcome {
+            outcome.line = self.line;
+            outcome.line_end = self.line_end;
+            outcome.kind = self.kind;
+            outcome.defered = self.defered;
+            events::task_finished(&outcome);
+            progress::task_finished(&outcome);
+            outcome
+        }
+
+        pub fn execute_task(&self) -> TaskOutcome {
+            if is_cancelled() {
+                return self.skip(TaskStatus::Cancelled);
+            }
+
+            for (dep_id, dep) in self.dependencies.iter() {
+                logprint!(LogStatus::Trace, "task {} waits for task {dep_id}", self.id);
+                if !dep.wait() {
+                    let status = match is_cancelled() {
+                        true => TaskStatus::Cancelled,
+                        false => TaskStatus::DependencyFailed(*dep_id),
+                    };
+                    return self.skip(status);
+                }
             }
+
+            // the class slot is taken first, every task takes the slots in the same order
+            let class_slot = acquire_class_slot(self.class);
+            let slot = acquire_run_slot(self.exclusive);
+            // an interactive task has the terminal for itself
+            let pause = self.exclusive.then(progress::pause);
+            events::task_started(self);
+            progress::task_started(self.id, &self.label);
+            logprint!(
+                LogStatus::Trace,
+                "task {} ({}) started",
+                self.id,
+                self.label
+            );
+            let start = Instant::now();
+            let mut outcome = (self._task_fn)();
+            outcome.duration = start.elapsed();
+
+            drop(pause);
+            drop(slot);
+            drop(class_slot);
+
+            self.signal.finish(outcome.succeeded());
+            self.outcome(outcome)
         }
     }
 }
@@ -0,0 +1,443 @@
+//! finds the templates by their name, `tmplt gen go-api` generates the first `go-api.tmplt` of
+//! the search path:
+//! - the `.tmplt/` directory of the project (the closest one in the current directory or its parents)
+//! - the user templates directory, `templates/` next to the user config (`~/.config/tmplt/templates`)
+//! - the directories of `--template-path` (or `template-path` in the config)
+//! - the directories of `TMPLT_PATH`, separated like `PATH`
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::arg_parser::args::{suggest, Options};
+use crate::config::user_config_path;
+use crate::core::{self, Metadata, Version};
+use crate::logger::console::{self, Stream};
+use crate::logger::writer::LogStatus;
+use crate::logprint;
+
+/// the extension of the templates
+pub const EXTENSION: &str = "tmplt";
+
+/// the name of the directory of the project templates
+pub const PROJECT_TEMPLATES: &str = ".tmplt";
+
+/// a template found in the search path
+#[derive(Clone, Debug)]
+pub struct TemplateInfo {
+    /// the name it is generated with, the file name without `.tmplt`
+    pub name: String,
+    pub path: PathBuf,
+    /// the `__META` section, empty if the template doesn't have one or can't be parsed
+    pub metadata: Metadata,
+    pub description: Option<String>,
+}
+
+/// the directories searched for the templates, in order
+pub fn search_path(args: &Options) -> Vec<PathBuf> {
+    search_dirs(
+        env::current_dir().ok().as_deref(),
+        user_config_path(),
+        args.get_template_paths_flag(),
+        env::var_os("TMPLT_PATH"),
+    )
+}
+
+/// the search path from the current directory, the user config, `--template-path` and `TMPLT_PATH`
+fn search_dirs(
+    cwd: Option<&Path>,
+    user_config: Option<PathBuf>,
+    template_paths: &[PathBuf],
+    tmplt_path: Option<OsString>,
+) -> Vec<PathBuf> {
+    let mut dirs = vec![];
+
+    if let Some(dir) = cwd.and_then(|cwd| {
+        cwd.ancestors()
+            .map(|dir| dir.join(PROJECT_TEMPLATES))
+            .find(|dir| dir.is_dir())
+    }) {
+        dirs.push(dir)
+    }
+    if let Some(dir) = user_config.and_then(|config| Some(config.parent()?.join("templates"))) {
+        dirs.push(dir)
+    }
+    dirs.extend(template_paths.iter().cloned());
+    if let Some(paths) = tmplt_path {
+        dirs.extend(env::split_paths(&paths).filter(|dir| !dir.as_os_str().is_empty()));
+    }
+
+    dirs
+}
+
+/// the file of the template, either the path that was given or the first template with this name
+/// in the search path (`go-api` or `go-api.tmplt`)
+pub fn resolve(name: &str, args: &Options) -> Result<PathBuf, String> {
+    find_template(name, &search_path(args))
+}
+
+fn find_template(name: &str, dirs: &[PathBuf]) -> Result<PathBuf, String> {
+    let path = Path::new(name);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    // a path to a file that doesn't exist isn't searched
+    if path.components().count() > 1 {
+        return Err(format!("the template {name} doesn't exist"));
+    }
+
+    let file_name = match path.extension().is_some_and(|ext| ext == EXTENSION) {
+        true => name.to_string(),
+        false => format!("{name}.{EXTENSION}"),
+    };
+    if let Some(found) = dirs
+        .iter()
+        .map(|dir| dir.join(&file_name))
+        .find(|p| p.is_file())
+    {
+        logprint!(
+            LogStatus::Debug,
+            "found the template {name} at {}",
+            found.display()
+        );
+        return Ok(found);
+    }
+
+    let templates = templates_in(dirs);
+    let names = templates
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect::<Vec<&str>>();
+    let name = name.trim_end_matches(&format!(".{EXTENSION}"));
+    let mut error = format!(
+        "unable to find the template {name}, it isn't a file nor a template of the search path"
+    );
+    match suggest(name, &names) {
+        Some(suggestion) => error.push_str(&format!(", did you mean '{suggestion}'?")),
+        None if dirs.is_empty() => (),
+        None => error.push_str(&format!(
+            " ({})",
+            dirs.iter()
+                .map(|dir| dir.display().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+    }
+    Err(error)
+}
+
+/// the templates of the search path, a template hides the ones with the same name that come after it
+pub fn list_templates(args: &Options) -> Vec<TemplateInfo> {
+    templates_in(&search_path(args))
+}
+
+fn templates_in(dirs: &[PathBuf]) -> Vec<TemplateInfo> {
+    let mut templates: Vec<TemplateInfo> = vec![];
+
+    for dir in dirs {
+        let mut paths = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_file() && path.extension().is_some_and(|ext| ext == EXTENSION)
+                })
+                .collect::<Vec<PathBuf>>(),
+            Err(_) => continue,
+        };
+        paths.sort();
+
+        for path in paths {
+            let name = match path.file_stem() {
+                Some(stem) => stem.to_string_lossy().to_string(),
+                None => continue,
+            };
+            if templates.iter().any(|t| t.name == name) {
+                continue;
+            }
+
+            let metadata = read_metadata(&path).unwrap_or_default();
+            templates.push(TemplateInfo {
+                name,
+                description: metadata.description.clone().or_else(|| describe(&path)),
+                metadata,
+                path,
+            })
+        }
+    }
+
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// the `__META` section of the template
+pub fn read_metadata(path: &Path) -> Result<Metadata, String> {
+    let lines = core::Parser::new(EXTENSION.to_string())
+        .parse_file(path.display().to_string())
+        .map_err(|e| format!("unable to read {}: {e}", path.display()))?;
+    let tree = core::construct_tree(lines).map_err(|e| e.to_string())?;
+    Metadata::from_tree(&tree).map_err(|e| e.to_string())
+}
+
+/// the description of a template without a `__META` section, the first comment of the file
+fn describe(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    content
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())?
+        .strip_prefix("//")
+        .map(|comment| comment.trim().to_string())
+        .filter(|comment| !comment.is_empty())
+}
+
+/// `tmplt list`, prints the templates of the search path
+pub fn handle_list_command(args: &Options) {
+    let templates = list_templates(args);
+    if templates.is_empty() {
+        logprint!(
+            LogStatus::Info,
+            "no template found, the templates are searched in:"
+        );
+        for dir in search_path(args) {
+            logprint!(LogStatus::Info, "  {}", dir.display());
+        }
+        return;
+    }
+
+    let width = templates.iter().map(|t| t.name.len()).max().unwrap_or(0);
+    let version = |t: &TemplateInfo| t.metadata.version.clone().unwrap_or("-".to_string());
+    let version_width = templates
+        .iter()
+        .map(|t| version(t).len())
+        .max()
+        .unwrap_or(0);
+    for template in templates.iter() {
+        console::write_line(
+            Stream::Stdout,
+            &format!(
+                "{:<width$}  {:<version_width$}  {}  ({})",
+                template.name,
+                version(template),
+                template.description.as_deref().unwrap_or("-"),
+                template.path.display()
+            ),
+        )
+    }
+}
+
+/// `tmplt info <template>`, prints the metadata of the template and checks its requirements
+pub fn handle_info_command(args: &Options) -> Result<(), String> {
+    let name = args.get_file().ok_or(
+        "should've passed a template, a .tmplt file or the name of a template (see tmplt list)",
+    )?;
+    let path = resolve(&name, args)?;
+    let metadata = read_metadata(&path)?;
+
+    let field =
+        |key: &str, value: &str| console::write_line(Stream::Stdout, &format!("{key:<13}{value}"));
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string());
+    field(
+        "name:",
+        metadata.name.as_deref().or(stem.as_deref()).unwrap_or("-"),
+    );
+    field(
+        "description:",
+        metadata.description.as_deref().unwrap_or("-"),
+    );
+    field("version:", metadata.version.as_deref().unwrap_or("-"));
+    field("file:", &path.display().to_string());
+    match &metadata.min_tmplt_version {
+        Some(min) if *min > Version::current() => field(
+            "tmplt:",
+            &format!(
+                "{min} or newer, too old: this is tmplt {}",
+                Version::current()
+            ),
+        ),
+        Some(min) => field("tmplt:", &format!("{min} or newer")),
+        None => field("tmplt:", "any version"),
+    }
+
+    if metadata.requires.is_empty() {
+        field("requires:", "nothing");
+    }
+    for (i, requirement) in metadata.requires.iter().enumerate() {
+        let key = if i == 0 { "requires:" } else { "" };
+        match requirement.check() {
+            Ok(found) => field(key, &format!("{requirement}  ok ({})", found.display())),
+            Err(problem) => field(key, &format!("{requirement}  missing: {problem}")),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+    use crate::arg_parser::args::ArgsParser;
+
+    /// an empty directory in the temp directory, removed first if it was left by another run
+    fn empty_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("tmplt-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    /// a project with a `.tmplt/` directory, a user templates directory, a `--template-path`
+    /// and a `TMPLT_PATH` with empty entries, the search path is returned with the root
+    fn search_path_in(root: &Path) -> Vec<PathBuf> {
+        write(
+            &root.join("project/.tmplt/go-api.tmplt"),
+            "// the api of the project\n",
+        );
+        fs::create_dir_all(root.join("project/src")).unwrap();
+        write(
+            &root.join("config/templates/go-api.tmplt"),
+            "// the api of the user\n",
+        );
+        write(
+            &root.join("config/templates/web.tmplt"),
+            "__META:\n    description = a web app\n    version = 1.0.0\n",
+        );
+        write(&root.join("extra/cli.tmplt"), "__CWD:\n    FILE a\n");
+        write(&root.join("env/tool.tmplt"), "\n\n// a tool\n");
+        write(&root.join("env/b.tmplt"), "");
+        // not a template
+        write(&root.join("env/notes.txt"), "");
+
+        let tmplt_path =
+            env::join_paths([Path::new(""), &root.join("env"), Path::new("")]).unwrap();
+        search_dirs(
+            Some(&root.join("project/src")),
+            Some(root.join("config/config.toml")),
+            &[root.join("extra")],
+            Some(tmplt_path),
+        )
+    }
+
+    #[test]
+    fn the_project_templates_are_searched_first() {
+        let root = empty_dir("templates-order");
+        let dirs = search_path_in(&root);
+        assert_eq!(
+            dirs,
+            vec![
+                root.join("project/.tmplt"),
+                root.join("config/templates"),
+                root.join("extra"),
+                root.join("env"),
+            ]
+        );
+
+        // outside of the project there is no `.tmplt/` to search
+        assert_eq!(
+            search_dirs(Some(&root.join("extra")), None, &[], None),
+            Vec::<PathBuf>::new()
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_template_is_found_by_its_name() {
+        let root = empty_dir("templates-resolve");
+        let dirs = search_path_in(&root);
+
+        let api = root.join("project/.tmplt/go-api.tmplt");
+        assert_eq!(find_template("go-api", &dirs), Ok(api.clone()));
+        assert_eq!(find_template("go-api.tmplt", &dirs), Ok(api));
+        assert_eq!(
+            find_template("tool", &dirs),
+            Ok(root.join("env/tool.tmplt"))
+        );
+        // a path is used as it is
+        let cli = root.join("extra/cli.tmplt");
+        assert_eq!(find_template(&cli.display().to_string(), &dirs), Ok(cli));
+
+        // `b.tmplt` is in the search path but a path to a file that doesn't exist isn't searched
+        let error = find_template("a/b.tmplt", &dirs).unwrap_err();
+        assert_eq!(error, "the template a/b.tmplt doesn't exist");
+
+        let error = find_template("go-apj", &dirs).unwrap_err();
+        assert!(error.ends_with("did you mean 'go-api'?"), "{error}");
+        let error = find_template("nothing-like-it", &dirs).unwrap_err();
+        assert!(
+            error.contains(&root.join("env").display().to_string()),
+            "{error}"
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_template_hides_the_ones_with_the_same_name_after_it() {
+        let root = empty_dir("templates-list");
+        let dirs = search_path_in(&root);
+
+        let templates = templates_in(&dirs);
+        let names = templates
+            .iter()
+            .map(|t| (t.name.as_str(), t.description.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("b", None),
+                ("cli", None),
+                ("go-api", Some("the api of the project")),
+                ("tool", Some("a tool")),
+                ("web", Some("a web app")),
+            ]
+        );
+        assert_eq!(templates[2].path, root.join("project/.tmplt/go-api.tmplt"));
+        assert_eq!(templates[4].metadata.version.as_deref(), Some("1.0.0"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_template_is_described_by_its_first_comment() {
+        let root = empty_dir("templates-describe");
+        let path = root.join("a.tmplt");
+
+        write(&path, "\n  // a go api  \n// more\n__CWD:\n");
+        assert_eq!(describe(&path).as_deref(), Some("a go api"));
+        write(&path, "__CWD:\n// a go api\n");
+        assert_eq!(describe(&path), None);
+        write(&path, "//\n__CWD:\n");
+        assert_eq!(describe(&path), None);
+        assert_eq!(describe(&root.join("missing.tmplt")), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn a_leading_tilde_in_the_template_path_is_the_home_directory() {
+        let home = PathBuf::from(env::var_os("HOME").unwrap());
+        let args = ["tmplt", "list", "--template-path=~/templates"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<String>>();
+        let options = ArgsParser::new(args).parse_options(&[]).unwrap();
+
+        assert_eq!(
+            search_dirs(None, None, options.get_template_paths_flag(), None),
+            vec![home.join("templates")]
+        );
+    }
+}